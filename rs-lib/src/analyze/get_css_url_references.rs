@@ -0,0 +1,36 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static COMMENT_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"(?s)/\*.*?\*/").unwrap());
+/// Matches `@import "<url>"` and `url(<url>)` with a quoted or unquoted url.
+static REFERENCE_RE: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(
+    r#"(?i)@import\s*(?:"([^"]*)"|'([^']*)')|\burl\(\s*(?:"([^"]*)"|'([^']*)'|([^"'()\s]+))\s*\)"#,
+  )
+  .unwrap()
+});
+
+/// Gets the urls referenced by `@import` rules and `url()` functions in
+/// the stylesheet, excluding `data:` urls and fragment only urls (ex.
+/// `url(#filter)`). This is a shallow scan of the text, so escapes and
+/// urls built with `var()` aren't supported.
+pub fn get_css_url_references(text: &str) -> Vec<String> {
+  let text = COMMENT_RE.replace_all(text, "");
+  REFERENCE_RE
+    .captures_iter(&text)
+    .filter_map(|captures| {
+      let value = captures.iter().skip(1).flatten().next()?.as_str().trim();
+      if value.is_empty()
+        || value.starts_with('#')
+        || value.to_lowercase().starts_with("data:")
+      {
+        None
+      } else {
+        Some(value.to_string())
+      }
+    })
+    .collect()
+}
@@ -0,0 +1,94 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::swc::common::SyntaxContext;
+use deno_ast::view::*;
+use deno_ast::SourceRange;
+use deno_ast::SourceRanged;
+
+use super::get_str_lit_value;
+
+/// Gets the names and ranges of any property accesses on the `Deno`
+/// global (ex. `Deno.readTextFile`) when `Deno` isn't shadowed.
+pub fn get_deno_global_usages(
+  program: Program,
+  unresolved_context: SyntaxContext,
+) -> Vec<(String, SourceRange)> {
+  let mut results = Vec::new();
+  visit_children(program.into(), program, unresolved_context, &mut results);
+  results
+}
+
+fn visit_children(
+  node: Node,
+  program: Program,
+  unresolved_context: SyntaxContext,
+  results: &mut Vec<(String, SourceRange)>,
+) {
+  match node {
+    // ex. Deno.readTextFile or Deno["readTextFile"]
+    Node::MemberExpr(member_expr) => {
+      if is_deno_global(member_expr.obj, program, unresolved_context) {
+        let name = match member_expr.prop {
+          MemberProp::Ident(ident) => Some(ident.sym().to_string()),
+          MemberProp::Computed(computed) => get_str_lit_value(computed.expr),
+          MemberProp::PrivateName(_) => None,
+        };
+        if let Some(name) = name {
+          results.push((format!("Deno.{}", name), member_expr.range()));
+        }
+      }
+    }
+    // ex. const { readTextFile } = Deno;
+    Node::VarDeclarator(decl) => {
+      if let (Pat::Object(obj), Some(init)) = (&decl.name, decl.init) {
+        if is_deno_global(init, program, unresolved_context) {
+          for prop in obj.props {
+            let name = match prop {
+              ObjectPatProp::Assign(assign) => {
+                Some(assign.key.id.sym().to_string())
+              }
+              ObjectPatProp::KeyValue(key_value) => match key_value.key {
+                PropName::Ident(ident) => Some(ident.sym().to_string()),
+                PropName::Str(str) => Some(str.value().to_string()),
+                _ => None,
+              },
+              ObjectPatProp::Rest(_) => None,
+            };
+            if let Some(name) = name {
+              results.push((format!("Deno.{}", name), prop.range()));
+            }
+          }
+        }
+      }
+    }
+    _ => {}
+  }
+
+  for child in node.children() {
+    visit_children(child, program, unresolved_context, results);
+  }
+}
+
+fn is_deno_global(
+  expr: Expr,
+  program: Program,
+  unresolved_context: SyntaxContext,
+) -> bool {
+  match expr {
+    Expr::Ident(ident) => {
+      ident.ctxt() == unresolved_context && ident.text_fast(program) == "Deno"
+    }
+    // ex. globalThis.Deno
+    Expr::Member(member_expr) => {
+      matches!(
+        member_expr.obj,
+        Expr::Ident(ident) if ident.ctxt() == unresolved_context
+          && ident.text_fast(program) == "globalThis"
+      ) && matches!(
+        member_expr.prop,
+        MemberProp::Ident(ident) if ident.sym() == "Deno"
+      )
+    }
+    _ => false,
+  }
+}
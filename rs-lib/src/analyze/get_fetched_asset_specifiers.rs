@@ -0,0 +1,41 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::swc::common::SyntaxContext;
+use deno_ast::view::*;
+
+use super::get_import_meta_url_relative_path;
+
+/// Gets the string literal paths of any global
+/// `fetch(new URL("<path>", import.meta.url))` calls.
+pub fn get_fetched_asset_specifiers(
+  program: Program,
+  unresolved_context: SyntaxContext,
+) -> Vec<String> {
+  let mut results = Vec::new();
+  visit_children(program.into(), unresolved_context, &mut results);
+  results
+}
+
+fn visit_children(
+  node: Node,
+  unresolved_context: SyntaxContext,
+  results: &mut Vec<String>,
+) {
+  if let Node::CallExpr(call_expr) = node {
+    if let Callee::Expr(Expr::Ident(ident)) = call_expr.callee {
+      if ident.sym() == "fetch" && ident.ctxt() == unresolved_context {
+        if let Some(value) = call_expr
+          .args
+          .first()
+          .and_then(|a| get_import_meta_url_relative_path(a.expr))
+        {
+          results.push(value);
+        }
+      }
+    }
+  }
+
+  for child in node.children() {
+    visit_children(child, unresolved_context, results);
+  }
+}
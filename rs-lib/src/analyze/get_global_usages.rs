@@ -0,0 +1,65 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::swc::common::SyntaxContext;
+use deno_ast::view::*;
+use deno_ast::SourceRange;
+use deno_ast::SourceRanged;
+
+use super::is_in_type;
+
+/// Gets the names and ranges of any unshadowed references to the
+/// provided globals, including via `globalThis` (ex. `globalThis.location`).
+pub fn get_global_usages(
+  program: Program,
+  unresolved_context: SyntaxContext,
+  globals: &[&str],
+) -> Vec<(String, SourceRange)> {
+  let mut results = Vec::new();
+  visit_children(
+    program.into(),
+    program,
+    unresolved_context,
+    globals,
+    &mut results,
+  );
+  results
+}
+
+fn visit_children(
+  node: Node,
+  program: Program,
+  unresolved_context: SyntaxContext,
+  globals: &[&str],
+  results: &mut Vec<(String, SourceRange)>,
+) {
+  match node {
+    Node::Ident(ident) => {
+      if ident.ctxt() == unresolved_context && !is_in_type(node) {
+        let text = ident.text_fast(program);
+        if globals.contains(&text) {
+          results.push((text.to_string(), ident.range()));
+        }
+      }
+    }
+    // ex. globalThis.location
+    Node::MemberExpr(member_expr) => {
+      if let (Expr::Ident(obj), MemberProp::Ident(prop)) =
+        (member_expr.obj, member_expr.prop)
+      {
+        if obj.ctxt() == unresolved_context
+          && obj.text_fast(program) == "globalThis"
+        {
+          let text = prop.text_fast(program);
+          if globals.contains(&text) {
+            results.push((text.to_string(), member_expr.range()));
+          }
+        }
+      }
+    }
+    _ => {}
+  }
+
+  for child in node.children() {
+    visit_children(child, program, unresolved_context, globals, results);
+  }
+}
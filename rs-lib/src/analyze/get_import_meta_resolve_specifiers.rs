@@ -0,0 +1,37 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::view::*;
+
+use super::get_str_lit_value;
+use super::is_import_meta;
+
+/// Gets the string literal arguments of any `import.meta.resolve(...)` calls.
+pub fn get_import_meta_resolve_specifiers(program: Program) -> Vec<String> {
+  let mut results = Vec::new();
+  visit_children(program.into(), &mut results);
+  results
+}
+
+fn visit_children(node: Node, results: &mut Vec<String>) {
+  if let Node::CallExpr(call_expr) = node {
+    if let Callee::Expr(Expr::Member(member_expr)) = call_expr.callee {
+      let is_resolve_prop = matches!(
+        member_expr.prop,
+        MemberProp::Ident(ident) if ident.sym() == "resolve"
+      );
+      if is_resolve_prop && is_import_meta(member_expr.obj) {
+        if let Some(value) = call_expr
+          .args
+          .first()
+          .and_then(|a| get_str_lit_value(a.expr))
+        {
+          results.push(value);
+        }
+      }
+    }
+  }
+
+  for child in node.children() {
+    visit_children(child, results);
+  }
+}
@@ -0,0 +1,29 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::view::*;
+use deno_ast::SourceRange;
+use deno_ast::SourceRanged;
+
+use super::is_import_meta;
+
+/// Gets the names and ranges of any `import.meta` property accesses
+/// (ex. `import.meta.dirname`).
+pub fn get_import_meta_usages(program: Program) -> Vec<(String, SourceRange)> {
+  let mut results = Vec::new();
+  visit_children(program.into(), &mut results);
+  results
+}
+
+fn visit_children(node: Node, results: &mut Vec<(String, SourceRange)>) {
+  if let Node::MemberExpr(member_expr) = node {
+    if let MemberProp::Ident(ident) = member_expr.prop {
+      if is_import_meta(member_expr.obj) {
+        results.push((ident.sym().to_string(), member_expr.range()));
+      }
+    }
+  }
+
+  for child in node.children() {
+    visit_children(child, results);
+  }
+}
@@ -0,0 +1,70 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::view::*;
+
+use super::add_pat_names;
+use super::get_export_name;
+
+#[derive(Debug, Default)]
+pub struct ModuleExports {
+  /// Names of the exports declared or re-exported by name in the module.
+  pub names: Vec<String>,
+  /// Specifiers of the `export * from "..."` declarations.
+  pub star_specifiers: Vec<String>,
+}
+
+pub fn get_module_exports(program: Program) -> ModuleExports {
+  let mut exports = ModuleExports::default();
+  for child in program.children() {
+    match child {
+      Node::ExportDecl(export_decl) => match export_decl.decl {
+        Decl::Class(decl) => exports.names.push(decl.ident.sym().to_string()),
+        Decl::Fn(decl) => exports.names.push(decl.ident.sym().to_string()),
+        Decl::Var(decl) => {
+          for declarator in decl.decls {
+            add_pat_names(declarator.name, &mut exports.names);
+          }
+        }
+        Decl::TsInterface(decl) => {
+          exports.names.push(decl.id.sym().to_string())
+        }
+        Decl::TsTypeAlias(decl) => {
+          exports.names.push(decl.id.sym().to_string())
+        }
+        Decl::TsEnum(decl) => exports.names.push(decl.id.sym().to_string()),
+        Decl::TsModule(decl) => {
+          if let TsModuleName::Ident(ident) = decl.id {
+            exports.names.push(ident.sym().to_string());
+          }
+        }
+        Decl::Using(_) => {}
+      },
+      Node::ExportDefaultDecl(_) | Node::ExportDefaultExpr(_) => {
+        exports.names.push("default".to_string());
+      }
+      Node::NamedExport(named_export) => {
+        for specifier in named_export.specifiers {
+          let name = match specifier {
+            ExportSpecifier::Named(named) => {
+              get_export_name(named.exported.unwrap_or(named.orig))
+            }
+            ExportSpecifier::Namespace(namespace) => {
+              get_export_name(namespace.name)
+            }
+            ExportSpecifier::Default(default) => {
+              default.exported.sym().to_string()
+            }
+          };
+          exports.names.push(name);
+        }
+      }
+      Node::ExportAll(export_all) => {
+        exports
+          .star_specifiers
+          .push(export_all.src.value().to_string());
+      }
+      _ => {}
+    }
+  }
+  exports
+}
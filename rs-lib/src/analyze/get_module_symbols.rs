@@ -0,0 +1,226 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::collections::HashMap;
+
+use deno_ast::swc::ast::Id;
+use deno_ast::view::*;
+use deno_ast::SourceRange;
+use deno_ast::SourceRanged;
+
+use super::get_export_name;
+use super::visit_pat_idents;
+
+/// The top level declarations, import bindings, and exports of a module.
+#[derive(Debug, Default)]
+pub struct ModuleSymbolInfo {
+  /// Top level declarations keyed by their name and syntax context, so
+  /// they can be told apart from other bindings with the same name.
+  pub declarations: HashMap<Id, SourceRange>,
+  pub imports: Vec<SymbolImport>,
+  pub exports: Vec<SymbolExport>,
+}
+
+#[derive(Debug)]
+pub struct SymbolImport {
+  pub local: Id,
+  /// The name of the export, which is `default` for default imports
+  /// and `*` for namespace imports.
+  pub imported: String,
+  pub src: String,
+  pub range: SourceRange,
+}
+
+#[derive(Debug)]
+pub enum SymbolExport {
+  /// An export of a binding in the module, which is `None` for default
+  /// exports of anonymous declarations and expressions.
+  Local {
+    name: String,
+    local: Option<Id>,
+    range: SourceRange,
+  },
+  /// An export of another module's export (ex. `export { a } from "./a.ts"`),
+  /// where `imported` is `*` for namespace re-exports.
+  ReExport {
+    name: String,
+    imported: String,
+    src: String,
+    range: SourceRange,
+  },
+  /// `export * from "..."`
+  Star { src: String },
+}
+
+pub fn get_module_symbols(program: Program) -> ModuleSymbolInfo {
+  let mut symbols = ModuleSymbolInfo::default();
+  for child in program.children() {
+    match child {
+      Node::ImportDecl(import_decl) => {
+        let src = import_decl.src.value().to_string();
+        for specifier in import_decl.specifiers {
+          let (local, imported) = match specifier {
+            ImportSpecifier::Named(named) => (
+              named.local,
+              named
+                .imported
+                .map(get_export_name)
+                .unwrap_or_else(|| named.local.sym().to_string()),
+            ),
+            ImportSpecifier::Default(default) => {
+              (default.local, "default".to_string())
+            }
+            ImportSpecifier::Namespace(namespace) => {
+              (namespace.local, "*".to_string())
+            }
+          };
+          symbols.imports.push(SymbolImport {
+            local: local.inner.to_id(),
+            imported,
+            src: src.clone(),
+            range: local.range(),
+          });
+        }
+      }
+      Node::ExportDecl(export_decl) => {
+        let mut declarations = HashMap::new();
+        add_decl(export_decl.decl, &mut declarations);
+        let mut names = declarations.into_iter().collect::<Vec<_>>();
+        names.sort_by_key(|(_, range)| range.start);
+        for (id, range) in names {
+          symbols.declarations.insert(id.clone(), range);
+          symbols.exports.push(SymbolExport::Local {
+            name: id.0.to_string(),
+            local: Some(id),
+            range,
+          });
+        }
+      }
+      Node::ExportDefaultDecl(export_default_decl) => {
+        let ident = match export_default_decl.decl {
+          DefaultDecl::Class(expr) => expr.ident,
+          DefaultDecl::Fn(expr) => expr.ident,
+          DefaultDecl::TsInterfaceDecl(decl) => Some(decl.id),
+        };
+        if let Some(ident) = ident {
+          symbols
+            .declarations
+            .insert(ident.inner.to_id(), ident.range());
+        }
+        symbols.exports.push(SymbolExport::Local {
+          name: "default".to_string(),
+          local: ident.map(|i| i.inner.to_id()),
+          range: ident
+            .map(|i| i.range())
+            .unwrap_or_else(|| export_default_decl.range()),
+        });
+      }
+      Node::ExportDefaultExpr(export_default_expr) => {
+        symbols.exports.push(SymbolExport::Local {
+          name: "default".to_string(),
+          local: match export_default_expr.expr {
+            Expr::Ident(ident) => Some(ident.inner.to_id()),
+            _ => None,
+          },
+          range: export_default_expr.range(),
+        });
+      }
+      Node::NamedExport(named_export) => {
+        let src = named_export.src.map(|s| s.value().to_string());
+        for specifier in named_export.specifiers {
+          let (exported, orig) = match specifier {
+            ExportSpecifier::Named(named) => {
+              (named.exported.unwrap_or(named.orig), Some(named.orig))
+            }
+            ExportSpecifier::Namespace(namespace) => (namespace.name, None),
+            ExportSpecifier::Default(default) => {
+              symbols.exports.push(match &src {
+                Some(src) => SymbolExport::ReExport {
+                  name: default.exported.sym().to_string(),
+                  imported: "default".to_string(),
+                  src: src.clone(),
+                  range: default.exported.range(),
+                },
+                None => SymbolExport::Local {
+                  name: default.exported.sym().to_string(),
+                  local: Some(default.exported.inner.to_id()),
+                  range: default.exported.range(),
+                },
+              });
+              continue;
+            }
+          };
+          let name = get_export_name(exported);
+          symbols.exports.push(match (&src, orig) {
+            (Some(src), orig) => SymbolExport::ReExport {
+              name,
+              imported: orig
+                .map(get_export_name)
+                .unwrap_or_else(|| "*".to_string()),
+              src: src.clone(),
+              range: exported.range(),
+            },
+            // local exports refer to a binding by identifier
+            (None, Some(ModuleExportName::Ident(ident))) => {
+              SymbolExport::Local {
+                name,
+                local: Some(ident.inner.to_id()),
+                range: exported.range(),
+              }
+            }
+            (None, _) => continue,
+          });
+        }
+      }
+      Node::ExportAll(export_all) => {
+        symbols.exports.push(SymbolExport::Star {
+          src: export_all.src.value().to_string(),
+        });
+      }
+      Node::FnDecl(decl) => add_decl(Decl::Fn(decl), &mut symbols.declarations),
+      Node::ClassDecl(decl) => {
+        add_decl(Decl::Class(decl), &mut symbols.declarations)
+      }
+      Node::VarDecl(decl) => {
+        add_decl(Decl::Var(decl), &mut symbols.declarations)
+      }
+      Node::TsInterfaceDecl(decl) => {
+        add_decl(Decl::TsInterface(decl), &mut symbols.declarations)
+      }
+      Node::TsTypeAliasDecl(decl) => {
+        add_decl(Decl::TsTypeAlias(decl), &mut symbols.declarations)
+      }
+      Node::TsEnumDecl(decl) => {
+        add_decl(Decl::TsEnum(decl), &mut symbols.declarations)
+      }
+      Node::TsModuleDecl(decl) => {
+        add_decl(Decl::TsModule(decl), &mut symbols.declarations)
+      }
+      _ => {}
+    }
+  }
+  symbols
+}
+
+fn add_decl(decl: Decl, declarations: &mut HashMap<Id, SourceRange>) {
+  let mut add_ident = |ident: &Ident| {
+    declarations.insert(ident.inner.to_id(), ident.range());
+  };
+  match decl {
+    Decl::Class(decl) => add_ident(decl.ident),
+    Decl::Fn(decl) => add_ident(decl.ident),
+    Decl::Var(decl) => {
+      for declarator in decl.decls {
+        visit_pat_idents(declarator.name, &mut add_ident);
+      }
+    }
+    Decl::TsInterface(decl) => add_ident(decl.id),
+    Decl::TsTypeAlias(decl) => add_ident(decl.id),
+    Decl::TsEnum(decl) => add_ident(decl.id),
+    Decl::TsModule(decl) => {
+      if let TsModuleName::Ident(ident) = decl.id {
+        add_ident(ident);
+      }
+    }
+    Decl::Using(_) => {}
+  }
+}
@@ -0,0 +1,26 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::view::*;
+use deno_ast::SourceRanged;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Matches a `/// <reference ... />` comment reference.
+static TRIPLE_SLASH_REFERENCE_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r"(?i)^/\s*<reference\s.*?/>").unwrap());
+/// Matches a lib reference (ex. `lib="dom"`).
+static LIB_REFERENCE_RE: Lazy<Regex> =
+  Lazy::new(|| Regex::new(r#"(?i)\slib\s*=\s*["']([^"']*)["']"#).unwrap());
+
+/// Gets the libs of the `/// <reference lib="..." />` directives at the
+/// top of the module, lowercased since lib names are case insensitive.
+pub fn get_referenced_libs(program: Program) -> Vec<String> {
+  program
+    .leading_comments_fast(program)
+    .filter(|comment| TRIPLE_SLASH_REFERENCE_RE.is_match(&comment.text))
+    .filter_map(|comment| {
+      let captures = LIB_REFERENCE_RE.captures(&comment.text)?;
+      Some(captures.get(1).unwrap().as_str().to_lowercase())
+    })
+    .collect()
+}
@@ -0,0 +1,53 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::swc::common::SyntaxContext;
+use deno_ast::view::*;
+use deno_ast::SourceRange;
+use deno_ast::SourceRanged;
+
+use super::get_str_lit_value;
+
+pub struct RequireCall {
+  /// The string literal argument or `None` if the argument is not
+  /// a string literal.
+  pub specifier: Option<String>,
+  /// Range of the argument if it's a string literal, otherwise the
+  /// range of the call.
+  pub range: SourceRange,
+}
+
+/// Gets any calls to the global `require` function.
+pub fn get_require_calls(
+  program: Program,
+  unresolved_context: SyntaxContext,
+) -> Vec<RequireCall> {
+  let mut results = Vec::new();
+  visit_children(program.into(), unresolved_context, &mut results);
+  results
+}
+
+fn visit_children(
+  node: Node,
+  unresolved_context: SyntaxContext,
+  results: &mut Vec<RequireCall>,
+) {
+  if let Node::CallExpr(call_expr) = node {
+    if let Callee::Expr(Expr::Ident(ident)) = call_expr.callee {
+      if ident.sym() == "require" && ident.ctxt() == unresolved_context {
+        let arg = call_expr.args.first().map(|a| a.expr);
+        let specifier = arg.and_then(get_str_lit_value);
+        results.push(RequireCall {
+          range: match (&specifier, arg) {
+            (Some(_), Some(arg)) => arg.range(),
+            _ => call_expr.range(),
+          },
+          specifier,
+        });
+      }
+    }
+  }
+
+  for child in node.children() {
+    visit_children(child, unresolved_context, results);
+  }
+}
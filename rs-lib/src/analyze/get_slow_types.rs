@@ -0,0 +1,290 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::collections::HashSet;
+
+use deno_ast::swc::ast::Accessibility;
+use deno_ast::swc::ast::MethodKind;
+use deno_ast::view::*;
+use deno_ast::SourceRange;
+use deno_ast::SourceRanged;
+
+use super::add_pat_names;
+
+/// Why the type of an exported declaration can't be known without
+/// inferring it from the implementation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlowTypeKind {
+  MissingReturnType,
+  MissingParamType,
+  MissingType,
+  /// A `export default` of an expression other than an identifier
+  /// or literal.
+  UnsupportedDefaultExport,
+}
+
+/// Gets the exported declarations of the module that are missing an
+/// explicit type, along with the name of the declaration
+/// (ex. `MyClass.method`). Only declarations exported where they're
+/// declared are checked.
+///
+/// This is a syntactic heuristic and not deno_graph's fast check, so it
+/// may not report the same declarations that publishing to JSR would.
+pub fn get_slow_types(
+  program: Program,
+) -> Vec<(SlowTypeKind, String, SourceRange)> {
+  let mut context = Context::default();
+  // the implementation of an overloaded function isn't part of its type
+  for child in program.children() {
+    if let Node::ExportDecl(export_decl) = child {
+      if let Decl::Fn(decl) = export_decl.decl {
+        if decl.function.body.is_none() {
+          context.overloads.insert(decl.ident.sym().to_string());
+        }
+      }
+    }
+  }
+
+  for child in program.children() {
+    match child {
+      Node::ExportDecl(export_decl) => match export_decl.decl {
+        Decl::Fn(decl) => {
+          let name = decl.ident.sym().to_string();
+          if decl.function.body.is_none() || !context.overloads.contains(&name)
+          {
+            context.check_function(&name, decl.ident.range(), decl.function);
+          }
+        }
+        Decl::Class(decl) => {
+          context.check_class(decl.ident.sym(), decl.class);
+        }
+        Decl::Var(decl) => {
+          for declarator in decl.decls {
+            context.check_var_declarator(declarator);
+          }
+        }
+        Decl::TsInterface(_)
+        | Decl::TsTypeAlias(_)
+        | Decl::TsEnum(_)
+        | Decl::TsModule(_)
+        | Decl::Using(_) => {}
+      },
+      Node::ExportDefaultDecl(export_default_decl) => match export_default_decl
+        .decl
+      {
+        DefaultDecl::Fn(expr) => context.check_function(
+          "default",
+          expr
+            .ident
+            .map(|i| i.range())
+            .unwrap_or(expr.function.range()),
+          expr.function,
+        ),
+        DefaultDecl::Class(expr) => context.check_class("default", expr.class),
+        DefaultDecl::TsInterfaceDecl(_) => {}
+      },
+      Node::ExportDefaultExpr(export_default_expr) => {
+        let expr = export_default_expr.expr;
+        if !matches!(expr, Expr::Ident(_)) && !is_simple_literal(expr) {
+          context.push(
+            SlowTypeKind::UnsupportedDefaultExport,
+            "default",
+            expr.range(),
+          );
+        }
+      }
+      _ => {}
+    }
+  }
+  context.diagnostics
+}
+
+#[derive(Default)]
+struct Context {
+  overloads: HashSet<String>,
+  diagnostics: Vec<(SlowTypeKind, String, SourceRange)>,
+}
+
+impl Context {
+  fn push(&mut self, kind: SlowTypeKind, name: &str, range: SourceRange) {
+    self.diagnostics.push((kind, name.to_string(), range));
+  }
+
+  fn check_function(
+    &mut self,
+    name: &str,
+    name_range: SourceRange,
+    function: &Function,
+  ) {
+    for param in function.params {
+      self.check_param(name, param.pat);
+    }
+    if function.return_type.is_none() {
+      self.push(SlowTypeKind::MissingReturnType, name, name_range);
+    }
+  }
+
+  fn check_param(&mut self, name: &str, pat: Pat) {
+    let has_type = match pat {
+      Pat::Ident(ident) => ident.type_ann.is_some(),
+      Pat::Array(array) => array.type_ann.is_some(),
+      Pat::Object(object) => object.type_ann.is_some(),
+      Pat::Rest(rest) => rest.type_ann.is_some(),
+      Pat::Assign(assign) => match assign.left {
+        Pat::Ident(ident) if ident.type_ann.is_none() => {
+          is_simple_literal(assign.right)
+        }
+        left => return self.check_param(name, left),
+      },
+      Pat::Invalid(_) | Pat::Expr(_) => true,
+    };
+    if !has_type {
+      self.push(SlowTypeKind::MissingParamType, name, pat.range());
+    }
+  }
+
+  fn check_class(&mut self, class_name: &str, class: &Class) {
+    for member in class.body {
+      match member {
+        ClassMember::Constructor(ctor) => {
+          if is_private(ctor.accessibility()) {
+            continue;
+          }
+          let name = format!("{}.constructor", class_name);
+          for param in ctor.params {
+            match param {
+              ParamOrTsParamProp::Param(param) => {
+                self.check_param(&name, param.pat)
+              }
+              ParamOrTsParamProp::TsParamProp(prop) => match prop.param {
+                TsParamPropParam::Ident(ident) => {
+                  self.check_param(&name, Pat::Ident(ident))
+                }
+                TsParamPropParam::Assign(assign) => {
+                  self.check_param(&name, Pat::Assign(assign))
+                }
+              },
+            }
+          }
+        }
+        ClassMember::Method(method) => {
+          if is_private(method.accessibility()) {
+            continue;
+          }
+          let Some(member_name) = get_prop_name(method.key) else {
+            continue;
+          };
+          let name = format!("{}.{}", class_name, member_name);
+          for param in method.function.params {
+            self.check_param(&name, param.pat);
+          }
+          if method.method_kind() != MethodKind::Setter
+            && method.function.return_type.is_none()
+          {
+            self.push(
+              SlowTypeKind::MissingReturnType,
+              &name,
+              method.key.range(),
+            );
+          }
+        }
+        ClassMember::ClassProp(prop) => {
+          if is_private(prop.accessibility()) {
+            continue;
+          }
+          let Some(member_name) = get_prop_name(prop.key) else {
+            continue;
+          };
+          if prop.type_ann.is_none()
+            && !prop.value.map(is_simple_literal).unwrap_or(false)
+          {
+            self.push(
+              SlowTypeKind::MissingType,
+              &format!("{}.{}", class_name, member_name),
+              prop.key.range(),
+            );
+          }
+        }
+        ClassMember::PrivateMethod(_)
+        | ClassMember::PrivateProp(_)
+        | ClassMember::TsIndexSignature(_)
+        | ClassMember::Empty(_)
+        | ClassMember::StaticBlock(_)
+        | ClassMember::AutoAccessor(_) => {}
+      }
+    }
+  }
+
+  fn check_var_declarator(&mut self, declarator: &VarDeclarator) {
+    let Pat::Ident(ident) = declarator.name else {
+      // destructured exports need a type annotation
+      if !has_type_ann(declarator.name) {
+        let mut names = Vec::new();
+        add_pat_names(declarator.name, &mut names);
+        self.push(
+          SlowTypeKind::MissingType,
+          &names.join(", "),
+          declarator.name.range(),
+        );
+      }
+      return;
+    };
+    if ident.type_ann.is_some() {
+      return;
+    }
+    let name = ident.id.sym().to_string();
+    match declarator.init {
+      Some(Expr::Arrow(arrow)) => {
+        for param in arrow.params {
+          self.check_param(&name, *param);
+        }
+        if arrow.return_type.is_none() {
+          self.push(SlowTypeKind::MissingReturnType, &name, ident.range());
+        }
+      }
+      Some(Expr::Fn(expr)) => {
+        self.check_function(&name, ident.range(), expr.function);
+      }
+      Some(Expr::TsAs(_)) | Some(Expr::TsTypeAssertion(_)) => {}
+      Some(expr) if is_simple_literal(expr) => {}
+      _ => self.push(SlowTypeKind::MissingType, &name, ident.range()),
+    }
+  }
+}
+
+fn has_type_ann(pat: Pat) -> bool {
+  match pat {
+    Pat::Ident(ident) => ident.type_ann.is_some(),
+    Pat::Array(array) => array.type_ann.is_some(),
+    Pat::Object(object) => object.type_ann.is_some(),
+    Pat::Rest(rest) => rest.type_ann.is_some(),
+    Pat::Assign(_) | Pat::Invalid(_) | Pat::Expr(_) => false,
+  }
+}
+
+/// Gets if the type of the expression is the same as what it's
+/// written as (ex. `"text"` or `-1`).
+fn is_simple_literal(expr: Expr) -> bool {
+  match expr {
+    Expr::Lit(lit) => !matches!(lit, Lit::Regex(_) | Lit::JSXText(_)),
+    Expr::Tpl(tpl) => tpl.exprs.is_empty(),
+    Expr::Unary(unary) => {
+      unary.op() == UnaryOp::Minus
+        && matches!(unary.arg, Expr::Lit(Lit::Num(_) | Lit::BigInt(_)))
+    }
+    Expr::Paren(paren) => is_simple_literal(paren.expr),
+    _ => false,
+  }
+}
+
+fn is_private(accessibility: Option<Accessibility>) -> bool {
+  accessibility == Some(Accessibility::Private)
+}
+
+fn get_prop_name(key: PropName) -> Option<String> {
+  match key {
+    PropName::Ident(ident) => Some(ident.sym().to_string()),
+    PropName::Str(str) => Some(str.value().to_string()),
+    PropName::Num(num) => Some(num.value().to_string()),
+    PropName::Computed(_) | PropName::BigInt(_) => None,
+  }
+}
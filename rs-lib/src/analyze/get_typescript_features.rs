@@ -0,0 +1,149 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::view::*;
+
+/// The TypeScript-only constructs used in a module.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TsFeatureSet {
+  /// Type annotations, type declarations, type assertions, `implements`
+  /// clauses, and type-only imports and exports.
+  pub type_annotations: bool,
+  pub enums: bool,
+  pub const_enums: bool,
+  /// Namespaces and ambient module declarations.
+  pub namespaces: bool,
+  /// Constructor parameters with an accessibility or `readonly` modifier.
+  pub parameter_properties: bool,
+  /// `abstract` classes and class members with a TypeScript-only modifier
+  /// (ex. `private`, `declare`, `readonly`, or `override`) or that are
+  /// optional or definitely assigned (ex. `a?: string` or `a!: string`).
+  pub class_modifiers: bool,
+  /// `import x = require("...")`, `import x = Ns.y`, and `export = x`.
+  pub import_export_assignments: bool,
+  pub decorators: bool,
+}
+
+impl TsFeatureSet {
+  /// Gets if no TypeScript-only constructs are used.
+  pub fn is_empty(&self) -> bool {
+    *self == Self::default()
+  }
+}
+
+pub fn get_typescript_features(program: Program) -> TsFeatureSet {
+  let mut features = TsFeatureSet::default();
+  visit_children(program.as_node(), &mut features);
+  features
+}
+
+fn visit_children(node: Node, features: &mut TsFeatureSet) {
+  for child in node.children() {
+    match child {
+      Node::TsTypeAnn(_)
+      | Node::TsInterfaceDecl(_)
+      | Node::TsTypeAliasDecl(_)
+      | Node::TsTypeParamDecl(_)
+      | Node::TsTypeParamInstantiation(_)
+      | Node::TsAsExpr(_)
+      | Node::TsSatisfiesExpr(_)
+      | Node::TsNonNullExpr(_)
+      | Node::TsTypeAssertion(_)
+      | Node::TsConstAssertion(_)
+      | Node::TsExprWithTypeArgs(_)
+      | Node::TsIndexSignature(_) => {
+        features.type_annotations = true;
+        // types may only contain other types
+        if matches!(
+          child,
+          Node::TsTypeAnn(_)
+            | Node::TsInterfaceDecl(_)
+            | Node::TsTypeAliasDecl(_)
+            | Node::TsTypeParamDecl(_)
+            | Node::TsTypeParamInstantiation(_)
+            | Node::TsExprWithTypeArgs(_)
+            | Node::TsIndexSignature(_)
+        ) {
+          continue;
+        }
+      }
+      Node::ImportDecl(import_decl) if import_decl.type_only() => {
+        features.type_annotations = true;
+      }
+      Node::ImportNamedSpecifier(specifier) if specifier.is_type_only() => {
+        features.type_annotations = true;
+      }
+      Node::NamedExport(named_export) if named_export.type_only() => {
+        features.type_annotations = true;
+      }
+      Node::ExportNamedSpecifier(specifier) if specifier.is_type_only() => {
+        features.type_annotations = true;
+      }
+      // ambient declarations and overload signatures
+      Node::VarDecl(decl) if decl.declare() => {
+        features.type_annotations = true;
+      }
+      Node::FnDecl(decl) if decl.declare() => {
+        features.type_annotations = true;
+      }
+      Node::ClassDecl(decl) if decl.declare() => {
+        features.type_annotations = true;
+      }
+      Node::Function(func) if func.body.is_none() => {
+        features.type_annotations = true;
+      }
+      Node::TsImportEqualsDecl(_) | Node::TsExportAssignment(_) => {
+        features.import_export_assignments = true;
+      }
+      Node::Class(class) if class.is_abstract() => {
+        features.class_modifiers = true;
+      }
+      Node::ClassProp(prop)
+        if prop.is_abstract()
+          || prop.accessibility().is_some()
+          || prop.declare()
+          || prop.readonly()
+          || prop.is_override()
+          || prop.is_optional()
+          || prop.definite() =>
+      {
+        features.class_modifiers = true;
+      }
+      Node::PrivateProp(prop)
+        if prop.readonly()
+          || prop.is_override()
+          || prop.is_optional()
+          || prop.definite() =>
+      {
+        features.class_modifiers = true;
+      }
+      Node::ClassMethod(method)
+        if method.is_abstract()
+          || method.accessibility().is_some()
+          || method.is_override()
+          || method.is_optional() =>
+      {
+        features.class_modifiers = true;
+      }
+      Node::Constructor(ctor) if ctor.accessibility().is_some() => {
+        features.class_modifiers = true;
+      }
+      Node::AutoAccessor(accessor)
+        if accessor.accessibility().is_some() || accessor.is_override() =>
+      {
+        features.class_modifiers = true;
+      }
+      Node::TsEnumDecl(enum_decl) => {
+        if enum_decl.is_const() {
+          features.const_enums = true;
+        } else {
+          features.enums = true;
+        }
+      }
+      Node::TsModuleDecl(_) => features.namespaces = true,
+      Node::TsParamProp(_) => features.parameter_properties = true,
+      Node::Decorator(_) => features.decorators = true,
+      _ => {}
+    }
+    visit_children(child, features);
+  }
+}
@@ -0,0 +1,33 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::view::*;
+use deno_ast::SourceRange;
+use deno_ast::SourceRanged;
+
+use super::get_str_lit_value;
+
+/// Gets the ranges of any dynamic imports whose specifier isn't a
+/// string literal (ex. `import(specifier)`).
+pub fn get_unanalyzable_dynamic_imports(program: Program) -> Vec<SourceRange> {
+  let mut results = Vec::new();
+  visit_children(program.into(), &mut results);
+  results
+}
+
+fn visit_children(node: Node, results: &mut Vec<SourceRange>) {
+  if let Node::CallExpr(call_expr) = node {
+    if matches!(call_expr.callee, Callee::Import(_))
+      && call_expr
+        .args
+        .first()
+        .and_then(|arg| get_str_lit_value(arg.expr))
+        .is_none()
+    {
+      results.push(call_expr.range());
+    }
+  }
+
+  for child in node.children() {
+    visit_children(child, results);
+  }
+}
@@ -0,0 +1,51 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::collections::HashSet;
+
+use deno_ast::swc::atoms::Atom;
+use deno_ast::swc::common::SyntaxContext;
+use deno_ast::view::*;
+use deno_ast::SourceRange;
+use deno_ast::SourceRanged;
+
+/// Gets the names and ranges of the local bindings of imports that are
+/// never referenced, including in type positions.
+pub fn get_unused_imports(program: Program) -> Vec<(String, SourceRange)> {
+  let mut imports = Vec::new();
+  for child in program.children() {
+    if let Node::ImportDecl(import_decl) = child {
+      for specifier in import_decl.specifiers {
+        let local = match specifier {
+          ImportSpecifier::Named(named) => named.local,
+          ImportSpecifier::Default(default) => default.local,
+          ImportSpecifier::Namespace(namespace) => namespace.local,
+        };
+        imports.push(local);
+      }
+    }
+  }
+  if imports.is_empty() {
+    return Vec::new();
+  }
+
+  let mut used = HashSet::new();
+  visit_children(program.as_node(), &mut used);
+  imports
+    .into_iter()
+    .filter(|local| !used.contains(&(local.sym().clone(), local.ctxt())))
+    .map(|local| (local.sym().to_string(), local.range()))
+    .collect()
+}
+
+fn visit_children(node: Node, used: &mut HashSet<(Atom, SyntaxContext)>) {
+  for child in node.children() {
+    match child {
+      // don't count the bindings themselves
+      Node::ImportDecl(_) => {}
+      Node::Ident(ident) => {
+        used.insert((ident.sym().clone(), ident.ctxt()));
+      }
+      _ => visit_children(child, used),
+    }
+  }
+}
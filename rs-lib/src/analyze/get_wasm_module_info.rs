@@ -0,0 +1,168 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+/// The imports and exports of a Wasm module, which are needed to
+/// instantiate it.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WasmModuleInfo {
+  pub imports: Vec<WasmImport>,
+  pub exports: Vec<WasmExport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmImport {
+  /// Name of the module the value is imported from (ex. `./env.js`).
+  pub module: String,
+  pub name: String,
+  pub kind: WasmExternalKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmExport {
+  pub name: String,
+  pub kind: WasmExternalKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WasmExternalKind {
+  Function,
+  Table,
+  Memory,
+  Global,
+  Tag,
+}
+
+/// Reads the import and export sections of a Wasm binary, returning
+/// `None` when the bytes are not a valid Wasm module.
+pub fn get_wasm_module_info(bytes: &[u8]) -> Option<WasmModuleInfo> {
+  const IMPORT_SECTION: u8 = 2;
+  const EXPORT_SECTION: u8 = 7;
+
+  let mut reader = Reader { bytes, pos: 0 };
+  if reader.read_bytes(4)? != b"\0asm" || reader.read_bytes(4)? != [1, 0, 0, 0]
+  {
+    return None;
+  }
+  let mut info = WasmModuleInfo::default();
+  while !reader.is_empty() {
+    let id = reader.read_byte()?;
+    let size = reader.read_u32()? as usize;
+    let mut section = Reader {
+      bytes: reader.read_bytes(size)?,
+      pos: 0,
+    };
+    match id {
+      IMPORT_SECTION => {
+        for _ in 0..section.read_u32()? {
+          let module = section.read_name()?;
+          let name = section.read_name()?;
+          let kind = section.read_import_desc()?;
+          info.imports.push(WasmImport { module, name, kind });
+        }
+      }
+      EXPORT_SECTION => {
+        for _ in 0..section.read_u32()? {
+          let name = section.read_name()?;
+          let kind = section.read_external_kind()?;
+          section.read_u32()?; // index
+          info.exports.push(WasmExport { name, kind });
+        }
+      }
+      _ => {}
+    }
+  }
+  Some(info)
+}
+
+struct Reader<'a> {
+  bytes: &'a [u8],
+  pos: usize,
+}
+
+impl<'a> Reader<'a> {
+  fn is_empty(&self) -> bool {
+    self.pos >= self.bytes.len()
+  }
+
+  fn read_byte(&mut self) -> Option<u8> {
+    let byte = *self.bytes.get(self.pos)?;
+    self.pos += 1;
+    Some(byte)
+  }
+
+  fn read_bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+    let bytes = self.bytes.get(self.pos..self.pos.checked_add(len)?)?;
+    self.pos += len;
+    Some(bytes)
+  }
+
+  /// Reads an unsigned LEB128 integer.
+  fn read_u64(&mut self) -> Option<u64> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+      let byte = self.read_byte()?;
+      if shift >= 64 {
+        return None;
+      }
+      value |= ((byte & 0x7f) as u64) << shift;
+      if byte & 0x80 == 0 {
+        return Some(value);
+      }
+      shift += 7;
+    }
+  }
+
+  fn read_u32(&mut self) -> Option<u32> {
+    self.read_u64()?.try_into().ok()
+  }
+
+  fn read_name(&mut self) -> Option<String> {
+    let len = self.read_u32()? as usize;
+    String::from_utf8(self.read_bytes(len)?.to_vec()).ok()
+  }
+
+  fn read_external_kind(&mut self) -> Option<WasmExternalKind> {
+    match self.read_byte()? {
+      0 => Some(WasmExternalKind::Function),
+      1 => Some(WasmExternalKind::Table),
+      2 => Some(WasmExternalKind::Memory),
+      3 => Some(WasmExternalKind::Global),
+      4 => Some(WasmExternalKind::Tag),
+      _ => None,
+    }
+  }
+
+  fn read_import_desc(&mut self) -> Option<WasmExternalKind> {
+    let kind = self.read_external_kind()?;
+    match kind {
+      WasmExternalKind::Function => {
+        self.read_u32()?; // type index
+      }
+      WasmExternalKind::Table => {
+        self.read_byte()?; // reference type
+        self.read_limits()?;
+      }
+      WasmExternalKind::Memory => {
+        self.read_limits()?;
+      }
+      WasmExternalKind::Global => {
+        self.read_byte()?; // value type
+        self.read_byte()?; // mutability
+      }
+      WasmExternalKind::Tag => {
+        self.read_byte()?; // attribute
+        self.read_u32()?; // type index
+      }
+    }
+    Some(kind)
+  }
+
+  fn read_limits(&mut self) -> Option<()> {
+    let flags = self.read_byte()?;
+    self.read_u64()?; // minimum
+    if flags & 1 != 0 {
+      self.read_u64()?; // maximum
+    }
+    Some(())
+  }
+}
@@ -0,0 +1,114 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::swc::ast::UnaryOp;
+use deno_ast::view::*;
+
+/// Gets if the program has top level statements other than imports,
+/// exports, and declarations without side effects (ex. a top level call).
+pub fn has_top_level_side_effects(program: Program) -> bool {
+  program.children().into_iter().any(has_side_effects)
+}
+
+fn has_side_effects(node: Node) -> bool {
+  match node {
+    Node::ImportDecl(_)
+    | Node::ExportAll(_)
+    | Node::NamedExport(_)
+    | Node::ExportDefaultDecl(_)
+    | Node::FnDecl(_)
+    | Node::EmptyStmt(_)
+    | Node::TsInterfaceDecl(_)
+    | Node::TsTypeAliasDecl(_)
+    | Node::TsEnumDecl(_)
+    | Node::TsModuleDecl(_)
+    | Node::TsImportEqualsDecl(_)
+    | Node::TsNamespaceExportDecl(_) => false,
+    Node::ClassDecl(decl) => class_has_side_effects(decl.class),
+    Node::VarDecl(decl) => var_decl_has_side_effects(decl),
+    Node::ExportDecl(export_decl) => match export_decl.decl {
+      Decl::Var(decl) => var_decl_has_side_effects(decl),
+      Decl::Class(decl) => class_has_side_effects(decl.class),
+      Decl::Fn(_)
+      | Decl::TsInterface(_)
+      | Decl::TsTypeAlias(_)
+      | Decl::TsEnum(_)
+      | Decl::TsModule(_)
+      | Decl::Using(_) => false,
+    },
+    Node::ExportDefaultExpr(export_default) => !is_pure(export_default.expr),
+    Node::TsExportAssignment(assignment) => !is_pure(assignment.expr),
+    // includes directives like "use strict"
+    Node::ExprStmt(stmt) => !is_pure(stmt.expr),
+    _ => true,
+  }
+}
+
+fn var_decl_has_side_effects(decl: &VarDecl) -> bool {
+  decl
+    .decls
+    .iter()
+    .any(|d| d.init.map(|init| !is_pure(init)).unwrap_or(false))
+}
+
+fn class_has_side_effects(class: &Class) -> bool {
+  !class.decorators.is_empty()
+    || class.super_class.map(|e| !is_pure(e)).unwrap_or(false)
+    || class.body.iter().any(|member| match member {
+      ClassMember::StaticBlock(_) => true,
+      ClassMember::ClassProp(prop) => {
+        !prop.decorators.is_empty()
+          || prop.is_static()
+            && prop.value.map(|value| !is_pure(value)).unwrap_or(false)
+      }
+      _ => false,
+    })
+}
+
+/// Gets if evaluating the expression is known to not have side effects.
+fn is_pure(expr: Expr) -> bool {
+  match expr {
+    Expr::Lit(_)
+    | Expr::Ident(_)
+    | Expr::This(_)
+    | Expr::Arrow(_)
+    | Expr::Fn(_) => true,
+    Expr::Class(class_expr) => !class_has_side_effects(class_expr.class),
+    Expr::Tpl(tpl) => tpl.exprs.iter().all(|e| is_pure(*e)),
+    Expr::Array(array) => array.elems.iter().all(|elem| match elem {
+      Some(elem) => elem.spread().is_none() && is_pure(elem.expr),
+      None => true,
+    }),
+    Expr::Object(object) => object.props.iter().all(|prop| match prop {
+      PropOrSpread::Spread(_) => false,
+      PropOrSpread::Prop(prop) => match prop {
+        Prop::KeyValue(key_value) => {
+          is_pure_prop_name(key_value.key) && is_pure(key_value.value)
+        }
+        Prop::Shorthand(_) => true,
+        Prop::Method(method) => is_pure_prop_name(method.key),
+        Prop::Getter(getter) => is_pure_prop_name(getter.key),
+        Prop::Setter(setter) => is_pure_prop_name(setter.key),
+        Prop::Assign(_) => false,
+      },
+    }),
+    Expr::Unary(unary) => unary.op() != UnaryOp::Delete && is_pure(unary.arg),
+    Expr::Bin(bin) => is_pure(bin.left) && is_pure(bin.right),
+    Expr::Cond(cond) => {
+      is_pure(cond.test) && is_pure(cond.cons) && is_pure(cond.alt)
+    }
+    Expr::Paren(paren) => is_pure(paren.expr),
+    Expr::TsAs(ts_as) => is_pure(ts_as.expr),
+    Expr::TsSatisfies(satisfies) => is_pure(satisfies.expr),
+    Expr::TsNonNull(non_null) => is_pure(non_null.expr),
+    Expr::TsConstAssertion(assertion) => is_pure(assertion.expr),
+    Expr::TsTypeAssertion(assertion) => is_pure(assertion.expr),
+    _ => false,
+  }
+}
+
+fn is_pure_prop_name(name: PropName) -> bool {
+  match name {
+    PropName::Computed(computed) => is_pure(computed.expr),
+    _ => true,
+  }
+}
@@ -1,5 +1,6 @@
 // Copyright 2018-2024 the Deno authors. MIT license.
 
+use deno_ast::swc::ast::MetaPropKind;
 use deno_ast::view::*;
 use deno_ast::SourceRanged;
 
@@ -197,3 +198,91 @@ pub fn is_in_type(mut node: Node) -> bool {
 
   false
 }
+
+/// Gets if the provided expression is `import.meta`.
+pub fn is_import_meta(expr: Expr) -> bool {
+  matches!(
+    expr,
+    Expr::MetaProp(meta_prop)
+      if meta_prop.prop_kind() == MetaPropKind::ImportMeta
+  )
+}
+
+/// Gets the value of the provided expression when it's a string literal.
+pub fn get_str_lit_value(expr: Expr) -> Option<String> {
+  match expr {
+    Expr::Lit(Lit::Str(str)) => Some(str.value().to_string()),
+    Expr::Tpl(tpl) if tpl.exprs.is_empty() && tpl.quasis.len() == 1 => {
+      tpl.quasis[0].cooked().as_ref().map(|c| c.to_string())
+    }
+    _ => None,
+  }
+}
+
+/// Gets the string literal path of a `new URL("<path>", import.meta.url)`
+/// expression.
+pub fn get_import_meta_url_relative_path(expr: Expr) -> Option<String> {
+  let Expr::New(new_expr) = expr else {
+    return None;
+  };
+  let is_url_callee =
+    matches!(new_expr.callee, Expr::Ident(ident) if ident.sym() == "URL");
+  let args = new_expr.args?;
+  if !is_url_callee || args.len() != 2 {
+    return None;
+  }
+  let is_import_meta_url = match args[1].expr {
+    Expr::Member(member_expr) => {
+      is_import_meta(member_expr.obj)
+        && matches!(
+          member_expr.prop,
+          MemberProp::Ident(ident) if ident.sym() == "url"
+        )
+    }
+    _ => false,
+  };
+  if is_import_meta_url {
+    get_str_lit_value(args[0].expr)
+  } else {
+    None
+  }
+}
+
+/// Adds the names of the bindings declared by the pattern.
+pub fn add_pat_names(pat: Pat, names: &mut Vec<String>) {
+  visit_pat_idents(pat, &mut |ident| names.push(ident.sym().to_string()));
+}
+
+/// Calls `visit` with the identifier of each binding declared by the pattern.
+pub fn visit_pat_idents(pat: Pat, visit: &mut impl FnMut(&Ident)) {
+  match pat {
+    Pat::Ident(ident) => visit(ident.id),
+    Pat::Array(array) => {
+      for elem in array.elems.iter().flatten() {
+        visit_pat_idents(*elem, visit);
+      }
+    }
+    Pat::Object(object) => {
+      for prop in object.props {
+        match prop {
+          ObjectPatProp::KeyValue(key_value) => {
+            visit_pat_idents(key_value.value, visit)
+          }
+          ObjectPatProp::Assign(assign) => visit(assign.key.id),
+          ObjectPatProp::Rest(rest) => visit_pat_idents(rest.arg, visit),
+        }
+      }
+    }
+    Pat::Rest(rest) => visit_pat_idents(rest.arg, visit),
+    Pat::Assign(assign) => visit_pat_idents(assign.left, visit),
+    Pat::Invalid(_) | Pat::Expr(_) => {}
+  }
+}
+
+/// Gets the name of an import or export specifier (ex. `a` or `"a"`).
+pub fn get_export_name(name: ModuleExportName) -> String {
+  match name {
+    ModuleExportName::Ident(ident) => ident.sym().to_string(),
+    ModuleExportName::Str(str) => str.value().to_string(),
+  }
+}
@@ -1,9 +1,41 @@
 // Copyright 2018-2024 the Deno authors. MIT license.
 
+mod get_css_url_references;
+mod get_deno_global_usages;
+mod get_fetched_asset_specifiers;
+mod get_global_usages;
 mod get_ignore_line_indexes;
+mod get_import_meta_resolve_specifiers;
+mod get_import_meta_usages;
+mod get_module_exports;
+mod get_module_symbols;
+mod get_referenced_libs;
+mod get_require_calls;
+mod get_slow_types;
 mod get_top_level_decls;
+mod get_typescript_features;
+mod get_unanalyzable_dynamic_imports;
+mod get_unused_imports;
+mod get_wasm_module_info;
+mod has_top_level_side_effects;
 mod helpers;
 
+pub use get_css_url_references::*;
+pub use get_deno_global_usages::*;
+pub use get_fetched_asset_specifiers::*;
+pub use get_global_usages::*;
 pub use get_ignore_line_indexes::*;
+pub use get_import_meta_resolve_specifiers::*;
+pub use get_import_meta_usages::*;
+pub use get_module_exports::*;
+pub use get_module_symbols::*;
+pub use get_referenced_libs::*;
+pub use get_require_calls::*;
+pub use get_slow_types::*;
 pub use get_top_level_decls::*;
+pub use get_typescript_features::*;
+pub use get_unanalyzable_dynamic_imports::*;
+pub use get_unused_imports::*;
+pub use get_wasm_module_info::*;
+pub use has_top_level_side_effects::*;
 pub use helpers::*;
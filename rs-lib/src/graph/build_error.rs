@@ -0,0 +1,147 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::fmt;
+
+use deno_ast::ModuleSpecifier;
+use deno_graph::ModuleError;
+use deno_graph::Range;
+use deno_graph::ResolutionError;
+
+use super::format_specifiers_for_message;
+
+/// An error building the module graph.
+#[derive(Debug)]
+pub enum GraphBuildError {
+  /// Modules that failed to load or whose dependencies failed to resolve,
+  /// in the order they were found.
+  Modules(Vec<ModuleLoadError>),
+  /// Specifiers that were indicated to be mapped to a module, but were
+  /// not found in the graph.
+  UnmappedModules(Vec<ModuleSpecifier>),
+  /// Specifiers that were indicated to be mapped to a package, but were
+  /// not found in the graph.
+  UnmappedPackages(Vec<ModuleSpecifier>),
+  /// The build was aborted via `ModuleGraphOptions::cancellation`.
+  Cancelled,
+  /// Any other error (ex. the import map failing to load).
+  Other(anyhow::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleLoadErrorKind {
+  NotFound,
+  Parse,
+  UnsupportedMediaType,
+  /// A dependency that couldn't be resolved (ex. it had a `${NAME}`
+  /// placeholder without a value).
+  Resolution,
+  /// Any other failure while loading the module (ex. a network error).
+  Load,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleLoadError {
+  pub kind: ModuleLoadErrorKind,
+  /// The module that failed to load, or the module with the dependency
+  /// that failed to resolve.
+  pub specifier: ModuleSpecifier,
+  /// Where the module was imported from.
+  pub referrer: Option<Range>,
+  pub message: String,
+}
+
+impl ModuleLoadError {
+  pub(crate) fn from_module_error(error: &ModuleError) -> Self {
+    let kind = match error {
+      ModuleError::Missing(..) | ModuleError::MissingDynamic(..) => {
+        ModuleLoadErrorKind::NotFound
+      }
+      ModuleError::ParseErr(..) => ModuleLoadErrorKind::Parse,
+      ModuleError::UnsupportedMediaType(..) => {
+        ModuleLoadErrorKind::UnsupportedMediaType
+      }
+      _ => ModuleLoadErrorKind::Load,
+    };
+    Self {
+      kind,
+      specifier: error.specifier().clone(),
+      referrer: error.maybe_referrer().cloned(),
+      message: format!("{:#}", error),
+    }
+  }
+
+  pub(crate) fn from_resolution_error(error: &ResolutionError) -> Self {
+    let range = error.range();
+    Self {
+      kind: ModuleLoadErrorKind::Resolution,
+      specifier: range.specifier.clone(),
+      referrer: Some(range.clone()),
+      message: error.to_string(),
+    }
+  }
+}
+
+impl fmt::Display for ModuleLoadError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let text = match &self.referrer {
+      Some(range) => format!("{}\n    at {}", self.message, range),
+      None => self.message.clone(),
+    };
+    write!(f, "{}", text)?;
+    if !text.contains(self.specifier.as_str()) {
+      write!(f, " ({})", self.specifier)?;
+    }
+    Ok(())
+  }
+}
+
+impl fmt::Display for GraphBuildError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      GraphBuildError::Modules(errors) => {
+        for (i, error) in errors.iter().enumerate() {
+          if i > 0 {
+            write!(f, "\n\n")?;
+          }
+          write!(f, "{}", error)?;
+        }
+        Ok(())
+      }
+      GraphBuildError::UnmappedModules(specifiers) => write!(
+        f,
+        "The following specifiers were indicated to be mapped to a module, but were not found:\n{}",
+        format_specifiers_for_message(specifiers.iter().collect()),
+      ),
+      GraphBuildError::UnmappedPackages(specifiers) => write!(
+        f,
+        "The following specifiers were indicated to be mapped to a package, but were not found:\n{}",
+        format_specifiers_for_message(specifiers.iter().collect()),
+      ),
+      GraphBuildError::Cancelled => {
+        write!(f, "The module graph build was cancelled.")
+      }
+      GraphBuildError::Other(err) => {
+        if f.alternate() {
+          write!(f, "{:#}", err)
+        } else {
+          write!(f, "{}", err)
+        }
+      }
+    }
+  }
+}
+
+impl std::error::Error for GraphBuildError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      GraphBuildError::Other(err) => err.source(),
+      _ => None,
+    }
+  }
+}
+
+impl From<anyhow::Error> for GraphBuildError {
+  fn from(err: anyhow::Error) -> Self {
+    GraphBuildError::Other(err)
+  }
+}
@@ -0,0 +1,330 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use anyhow::bail;
+use anyhow::Result;
+use deno_ast::MediaType;
+use deno_ast::ModuleSpecifier;
+use deno_graph::source::ResolutionMode;
+use deno_graph::source::ResolveError;
+use deno_graph::CapturingModuleAnalyzer;
+use deno_graph::Module;
+use deno_graph::Range;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::ModuleGraph;
+use crate::parser::ScopeAnalysisParser;
+
+/// Curated and stable JSON representation of a module graph.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphJson {
+  roots: Vec<ModuleSpecifier>,
+  /// Roots that are test entry points.
+  #[serde(default)]
+  test_roots: Vec<ModuleSpecifier>,
+  modules: Vec<ModuleJson>,
+  redirects: BTreeMap<ModuleSpecifier, ModuleSpecifier>,
+  externals: Vec<ModuleSpecifier>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModuleJson {
+  specifier: ModuleSpecifier,
+  kind: ModuleKindJson,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  media_type: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  source: Option<String>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  dependencies: Vec<DependencyJson>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  types_dependency: Option<ModuleSpecifier>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+enum ModuleKindJson {
+  Js,
+  Json,
+  Npm,
+  Node,
+  External,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DependencyJson {
+  specifier: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  code: Option<ModuleSpecifier>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  r#type: Option<ModuleSpecifier>,
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  is_dynamic: bool,
+}
+
+impl ModuleGraph {
+  /// Serializes the graph's modules, media types, dependency edges,
+  /// redirects, and external specifiers to JSON.
+  pub fn to_json(&self) -> serde_json::Value {
+    let modules = self
+      .graph
+      .modules()
+      .map(|module| match module {
+        Module::Js(module) => ModuleJson {
+          specifier: module.specifier.clone(),
+          kind: ModuleKindJson::Js,
+          media_type: Some(module.media_type.to_string()),
+          source: Some(module.source.to_string()),
+          dependencies: module
+            .dependencies
+            .iter()
+            .map(|(specifier, dep)| DependencyJson {
+              specifier: specifier.clone(),
+              code: dep.get_code().cloned(),
+              r#type: dep.get_type().cloned(),
+              is_dynamic: dep.is_dynamic,
+            })
+            .collect(),
+          types_dependency: module
+            .maybe_types_dependency
+            .as_ref()
+            .and_then(|d| d.dependency.maybe_specifier())
+            .cloned(),
+        },
+        Module::Json(module) => ModuleJson {
+          specifier: module.specifier.clone(),
+          kind: ModuleKindJson::Json,
+          media_type: Some(module.media_type.to_string()),
+          source: Some(module.source.to_string()),
+          dependencies: Vec::new(),
+          types_dependency: None,
+        },
+        Module::Npm(_) | Module::Node(_) | Module::External(_) => ModuleJson {
+          specifier: module.specifier().clone(),
+          kind: match module {
+            Module::Npm(_) => ModuleKindJson::Npm,
+            Module::Node(_) => ModuleKindJson::Node,
+            _ => ModuleKindJson::External,
+          },
+          media_type: None,
+          source: None,
+          dependencies: Vec::new(),
+          types_dependency: None,
+        },
+      })
+      .collect::<Vec<_>>();
+    let externals = modules
+      .iter()
+      .filter(|m| !matches!(m.kind, ModuleKindJson::Js | ModuleKindJson::Json))
+      .map(|m| m.specifier.clone())
+      .collect();
+    serde_json::to_value(GraphJson {
+      roots: self.graph.roots.clone(),
+      test_roots: self.test_entry_points.clone(),
+      modules,
+      redirects: self.graph.redirects.clone(),
+      externals,
+    })
+    .unwrap()
+  }
+
+  /// Rebuilds a graph from the output of `to_json` without loading
+  /// any sources from the original locations.
+  pub async fn from_json(value: serde_json::Value) -> Result<Self> {
+    let graph_json: GraphJson = serde_json::from_value(value)?;
+    // otherwise following the redirects while loading would never end
+    for specifier in graph_json.redirects.keys() {
+      let mut visited = HashSet::new();
+      let mut current = specifier;
+      while let Some(redirect) = graph_json.redirects.get(current) {
+        if redirect == current {
+          break;
+        }
+        if !visited.insert(current) {
+          bail!("The redirects of {} form a cycle.", specifier);
+        }
+        current = redirect;
+      }
+    }
+    let mut resolutions = HashMap::new();
+    let mut modules = HashMap::new();
+    for module in graph_json.modules {
+      for dep in module.dependencies.iter() {
+        resolutions.insert(
+          (module.specifier.clone(), dep.specifier.clone()),
+          (dep.code.clone(), dep.r#type.clone()),
+        );
+      }
+      modules.insert(module.specifier.clone(), module);
+    }
+
+    let mut loader = JsonLoader {
+      modules,
+      redirects: graph_json.redirects,
+    };
+    let resolver = JsonResolver { resolutions };
+    let source_parser = ScopeAnalysisParser::default();
+    let capturing_analyzer =
+      CapturingModuleAnalyzer::new(Some(Box::new(source_parser)), None);
+    let mut graph = deno_graph::ModuleGraph::new(deno_graph::GraphKind::All);
+    graph
+      .build(
+        graph_json.roots,
+        &mut loader,
+        deno_graph::BuildOptions {
+          resolver: Some(&resolver),
+          module_analyzer: Some(&capturing_analyzer),
+          module_parser: Some(&source_parser),
+          ..Default::default()
+        },
+      )
+      .await;
+    graph.valid()?;
+
+    let entry_points = graph
+      .roots
+      .iter()
+      .filter(|s| !graph_json.test_roots.contains(s))
+      .cloned()
+      .collect();
+    Ok(Self {
+      graph,
+      capturing_analyzer,
+      warnings: Vec::new(),
+      strip_query_and_fragment: false,
+      resolve_fallback: None,
+      entry_points,
+      test_entry_points: graph_json.test_roots,
+      failed_entry_points: Vec::new(),
+      assets: Default::default(),
+      cache_stats: Default::default(),
+      mapped_node_specifiers: Default::default(),
+      syntax: Default::default(),
+      mapped_modules: Default::default(),
+      encoding_errors: Default::default(),
+      resolution: Default::default(),
+    })
+  }
+}
+
+struct JsonLoader {
+  modules: HashMap<ModuleSpecifier, ModuleJson>,
+  redirects: BTreeMap<ModuleSpecifier, ModuleSpecifier>,
+}
+
+impl deno_graph::source::Loader for JsonLoader {
+  fn load(
+    &mut self,
+    specifier: &ModuleSpecifier,
+    _load_options: deno_graph::source::LoadOptions,
+  ) -> deno_graph::source::LoadFuture {
+    let mut specifier = specifier;
+    // follow the redirect chain to the final module
+    while let Some(redirect) = self.redirects.get(specifier) {
+      if redirect == specifier {
+        break;
+      }
+      specifier = redirect;
+    }
+    let response =
+      self.modules.get(specifier).map(|module| match module.kind {
+        ModuleKindJson::Js | ModuleKindJson::Json => {
+          let mut headers = HashMap::new();
+          if let Some(content_type) = module
+            .media_type
+            .as_deref()
+            .and_then(media_type_from_str)
+            .and_then(|m| m.as_content_type())
+          {
+            headers
+              .insert("content-type".to_string(), content_type.to_string());
+          }
+          if let Some(types_specifier) = &module.types_dependency {
+            headers.insert(
+              "x-typescript-types".to_string(),
+              types_specifier.to_string(),
+            );
+          }
+          deno_graph::source::LoadResponse::Module {
+            specifier: module.specifier.clone(),
+            content: Arc::from(
+              module.source.clone().unwrap_or_default().into_bytes(),
+            ),
+            maybe_headers: Some(headers),
+          }
+        }
+        ModuleKindJson::Npm
+        | ModuleKindJson::Node
+        | ModuleKindJson::External => {
+          deno_graph::source::LoadResponse::External {
+            specifier: module.specifier.clone(),
+          }
+        }
+      });
+    Box::pin(futures::future::ready(Ok(response)))
+  }
+}
+
+#[derive(Debug)]
+struct JsonResolver {
+  resolutions: HashMap<
+    (ModuleSpecifier, String),
+    (Option<ModuleSpecifier>, Option<ModuleSpecifier>),
+  >,
+}
+
+impl deno_graph::source::Resolver for JsonResolver {
+  fn resolve(
+    &self,
+    specifier: &str,
+    referrer_range: &Range,
+    mode: ResolutionMode,
+  ) -> Result<ModuleSpecifier, ResolveError> {
+    let key = (referrer_range.specifier.clone(), specifier.to_string());
+    let resolved = self.resolutions.get(&key).and_then(|(code, types)| {
+      if mode.is_types() {
+        types.as_ref().or(code.as_ref())
+      } else {
+        code.as_ref().or(types.as_ref())
+      }
+    });
+    match resolved {
+      Some(resolved) => Ok(resolved.clone()),
+      None => Ok(deno_graph::resolve_import(
+        specifier,
+        &referrer_range.specifier,
+      )?),
+    }
+  }
+}
+
+fn media_type_from_str(text: &str) -> Option<MediaType> {
+  use MediaType::*;
+  [
+    JavaScript,
+    Jsx,
+    Mjs,
+    Cjs,
+    TypeScript,
+    Mts,
+    Cts,
+    Dts,
+    Dmts,
+    Dcts,
+    Tsx,
+    Json,
+    Wasm,
+    TsBuildInfo,
+    SourceMap,
+  ]
+  .into_iter()
+  .find(|m| m.to_string() == text)
+}
@@ -0,0 +1,2226 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use crate::analyze::get_deno_global_usages;
+use crate::analyze::get_fetched_asset_specifiers;
+use crate::analyze::get_global_usages;
+use crate::analyze::get_import_meta_resolve_specifiers;
+use crate::analyze::get_import_meta_usages;
+use crate::analyze::get_module_exports;
+use crate::analyze::get_referenced_libs;
+use crate::analyze::get_require_calls;
+use crate::analyze::get_slow_types;
+use crate::analyze::get_typescript_features;
+use crate::analyze::get_unanalyzable_dynamic_imports;
+use crate::analyze::get_unused_imports;
+use crate::analyze::get_wasm_module_info;
+use crate::analyze::has_top_level_side_effects;
+use crate::analyze::SlowTypeKind;
+use crate::analyze::TsFeatureSet;
+use crate::analyze::WasmModuleInfo;
+use crate::loader::get_all_specifier_mappers;
+use crate::loader::get_cache_setting;
+use crate::loader::get_css_asset_references;
+use crate::loader::CacheStats;
+use crate::loader::EncodingError;
+use crate::loader::HostPattern;
+use crate::loader::Integrity;
+use crate::loader::JsonTransform;
+use crate::loader::LoadResponse;
+use crate::loader::Loader;
+use crate::loader::LoaderCache;
+use crate::loader::LoaderSpecifiers;
+use crate::loader::Lockfile;
+use crate::loader::RedirectCallback;
+use crate::loader::SharedCacheLoader;
+use crate::loader::SourceLoader;
+use crate::loader::SourceLoaderOptions;
+use crate::loader::VendorDir;
+use crate::parser::ScopeAnalysisParser;
+use crate::parser::SyntaxOptions;
+use crate::specifiers::get_specifiers;
+use crate::specifiers::Specifiers;
+use crate::utils::text_change_for_prepend_statement_to_text;
+use crate::visitors::get_module_specifier_text_changes;
+use crate::Dependency;
+use crate::MappedSpecifier;
+
+use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use deno_ast::apply_text_changes;
+use deno_ast::MediaType;
+use deno_ast::ModuleSpecifier;
+use deno_ast::ParsedSource;
+use deno_ast::SourceRange;
+use deno_graph::source::CacheSetting;
+use deno_graph::source::JsrUrlProvider;
+use deno_graph::source::LoaderChecksum;
+use deno_graph::source::Reporter;
+use deno_graph::source::ResolutionMode;
+use deno_graph::source::ResolveError;
+use deno_graph::source::Resolver;
+use deno_graph::CapturingModuleAnalyzer;
+use deno_graph::Module;
+use deno_graph::ModuleEntryRef;
+use deno_graph::ParsedSourceStore;
+use deno_graph::Position;
+use deno_graph::Range;
+use deno_graph::Resolution;
+use deno_semver::package::PackageNv;
+use deno_semver::package::PackageReq;
+use futures::future;
+use futures::future::AbortRegistration;
+use futures::future::Abortable;
+use futures::Future;
+use futures::StreamExt;
+use import_map::ImportMapOptions;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+mod build_error;
+#[cfg(feature = "serialization")]
+mod json;
+mod node_modules_resolver;
+mod symbol_graph;
+
+pub use build_error::*;
+use node_modules_resolver::NodeModulesResolver;
+use node_modules_resolver::PackageJsonError;
+pub use symbol_graph::*;
+
+pub struct ModuleGraphOptions<'a> {
+  pub entry_points: Vec<ModuleSpecifier>,
+  pub test_entry_points: Vec<ModuleSpecifier>,
+  pub loader: Option<Rc<dyn Loader>>,
+  pub specifier_mappings: &'a HashMap<ModuleSpecifier, MappedSpecifier>,
+  /// Import map to resolve specifiers with. It's parsed as JSONC, so it
+  /// may also use the JSON5 comments, trailing commas, single quoted
+  /// strings, and unquoted property names, but not other JSON5 syntax.
+  pub import_map: Option<ModuleSpecifier>,
+  /// Maximum number of bytes that may be loaded across all modules.
+  pub max_total_bytes: Option<usize>,
+  /// Expected digests of module contents in the subresource integrity
+  /// format (ex. `sha512-<base64 digest>`).
+  pub integrity: HashMap<ModuleSpecifier, String>,
+  /// Media type to use for modules whose media type can't be inferred
+  /// from their content type header or extension (ex. a `.xyz` file
+  /// served without a content type). This takes precedence over
+  /// `module_extensions`, so these files are parsed as this media type
+  /// instead of being loaded as assets.
+  pub default_media_type: Option<MediaType>,
+  /// Cache settings to use for remote modules matching a host pattern.
+  /// Unlisted hosts use the default cache setting.
+  pub cache_policy: HashMap<HostPattern, CacheSetting>,
+  /// Default cache setting for loading the import map and remote modules
+  /// (ex. `CacheSetting::Reload` to re-fetch them). Local files are
+  /// always read as usual.
+  pub cache_setting: CacheSetting,
+  /// Treat `file:` specifiers that only differ in casing as the same
+  /// module, as is the case on case-insensitive file systems (ex. macOS
+  /// and Windows by default). Modules use the casing of the file on disk
+  /// when the loader can list directories (see `Loader::list_dir`),
+  /// otherwise the casing they were first imported with.
+  pub case_insensitive_file_specifiers: bool,
+  /// Callback invoked with the original and redirected specifier for each
+  /// redirect followed by the default loader. Returning an error rejects
+  /// the redirect, which fails loading the module (ex. to reject redirects
+  /// to unexpected hosts). This is not used when providing a custom loader.
+  pub on_redirect: Option<Box<RedirectCallback>>,
+  /// Redirects that are already known (ex. from a lockfile). These are
+  /// applied before loading so the original specifier isn't requested.
+  pub known_redirects: BTreeMap<ModuleSpecifier, ModuleSpecifier>,
+  /// Remove the query string and fragment from specifiers returned by
+  /// `ModuleGraph::resolve_dependency`, which is what Node expects for
+  /// most file specifiers. Defaults to preserving them.
+  pub strip_query_and_fragment: bool,
+  /// Resolution to use in `ModuleGraph::resolve_dependency` when neither
+  /// the graph nor the built-in fallback resolution can resolve a specifier.
+  pub resolve_fallback: Option<Box<ResolveFallback>>,
+  /// Lowercase file extensions, without the leading period, of the files
+  /// that should be treated as modules. Files with any other extension are
+  /// loaded as assets and not parsed, unless their media type from the
+  /// content type header is JavaScript, TypeScript, or JSON, or their media
+  /// type is unknown and `default_media_type` is set. When empty, all files
+  /// are treated as modules. Defaults to `default_module_extensions`.
+  pub module_extensions: HashSet<String>,
+  /// Maximum number of modules the graph may contain.
+  pub max_modules: Option<usize>,
+  /// Enables resolving bare specifiers (ex. `lodash`) that aren't
+  /// otherwise resolved with node's resolution, looking in the
+  /// `node_modules` directories of the importing module's directory and
+  /// its ancestors, then in this directory. Packages are resolved with the
+  /// `exports` field of their package.json, including subpath patterns,
+  /// otherwise with the `main` field. The package.json files are loaded
+  /// with the loader and the directories are listed with
+  /// `Loader::list_dir`, so a loader that can't list directories won't
+  /// find any packages.
+  pub node_modules_dir: Option<PathBuf>,
+  /// Deno lockfile to get the versions of npm packages without a version
+  /// requirement (ex. `npm:chalk`) and of JSR packages from.
+  pub lockfile: Option<ModuleSpecifier>,
+  /// Version to use for npm packages without a version requirement that
+  /// aren't in the lockfile. Otherwise `*` is used.
+  pub default_package_version: Option<String>,
+  /// Provides the JSR registry URL that package metadata, such as the
+  /// latest version of an unversioned `jsr:` specifier, is loaded from.
+  /// Otherwise `https://jsr.io/` is used.
+  pub jsr_url_provider: Option<&'a dyn JsrUrlProvider>,
+  /// Warn about imports of absolute URLs that could instead be imported
+  /// with a bare specifier from the import map.
+  pub warn_import_map_bypass: bool,
+  /// Versions of the `@types/` packages to depend on for mapped packages
+  /// that don't provide their own types, keyed by the mapped package name
+  /// (ex. `lodash` to `^4.14.0` for `@types/lodash`).
+  pub types_packages: HashMap<String, String>,
+  /// Values to substitute for `${NAME}` placeholders in specifiers
+  /// before they're resolved (ex. `${BASE}/mod.ts`).
+  pub specifier_vars: HashMap<String, String>,
+  /// Directory created by `deno vendor` to load remote modules from
+  /// instead of the network, using the redirects of its import map.
+  pub vendor_dir: Option<ModuleSpecifier>,
+  /// Rewrites the value of each JSON module before it's added to the
+  /// graph (ex. to remove environment specific fields).
+  pub json_transform: Option<Box<JsonTransform>>,
+  /// Leave out the entry points that have a module that fails to load
+  /// instead of erroring, unless they all fail. The skipped entry points
+  /// are available via `ModuleGraph::failed_entry_points`.
+  pub skip_failed_entry_points: bool,
+  /// Patterns and replacements that rewrite remote specifiers to a
+  /// canonical form before they're resolved with the import map, so the
+  /// same package imported from different CDNs is only included once
+  /// (ex. `^https://ga\.jspm\.io/npm:(.+)$` to `https://unpkg.com/$1`).
+  /// Only the first matching pattern is used.
+  pub cdn_normalization: Vec<(Regex, String)>,
+  /// Notified after each module is loaded with the number of modules
+  /// loaded so far and the number of modules found so far.
+  pub reporter: Option<&'a dyn Reporter>,
+  /// Platform to prefer implementations for (ex. `node`). When resolving
+  /// a `file:` module like `./x.ts`, `./x.node.ts` is loaded instead if it
+  /// exists, falling back to `./x.ts` otherwise. The graph records this as
+  /// a redirect, so the platform file is the one that's output. Specifiers
+  /// that already name a platform file are loaded as-is. An error loading
+  /// the platform file is treated as it not existing. When the platform
+  /// file is loaded, it's checked against its own `integrity` entry rather
+  /// than the one for `./x.ts`.
+  pub platform_suffix: Option<String>,
+  /// Overrides of the syntax modules are parsed with (ex. to parse JSX
+  /// in `.js` files).
+  pub syntax: SyntaxOptions,
+  /// Resolve extensionless `file:` imports of a directory (ex. `./utils`)
+  /// to the first index file inside it with one of `index_extensions`,
+  /// like Node projects expect. This is off by default since Deno doesn't
+  /// do index resolution.
+  pub index_resolution: bool,
+  /// Extensions of the index files to try, in order, when
+  /// `index_resolution` is enabled. Defaults to `default_index_extensions`.
+  pub index_extensions: Vec<String>,
+  /// Re-serialize JSON modules with sorted keys and consistent formatting
+  /// so the output is the same regardless of how the source was formatted.
+  /// Numbers are kept as written and arrays aren't reordered. This runs
+  /// after `json_transform`.
+  pub canonicalize_json: bool,
+  /// Aborts the build when the corresponding `AbortHandle` is aborted,
+  /// which makes it error with `GraphBuildError::Cancelled`. Modules that
+  /// are still loading are dropped.
+  pub cancellation: Option<AbortRegistration>,
+  /// Scan CSS assets for the files they reference via `@import` and
+  /// `url()` (ex. `url("./img.png")`) and load those as assets too. Only
+  /// literal urls are found.
+  pub scan_css_assets: bool,
+}
+
+impl<'a> Default for ModuleGraphOptions<'a> {
+  fn default() -> Self {
+    static EMPTY_SPECIFIER_MAPPINGS: Lazy<
+      HashMap<ModuleSpecifier, MappedSpecifier>,
+    > = Lazy::new(HashMap::new);
+    Self {
+      entry_points: Vec::new(),
+      test_entry_points: Vec::new(),
+      loader: None,
+      specifier_mappings: &EMPTY_SPECIFIER_MAPPINGS,
+      import_map: None,
+      max_total_bytes: None,
+      integrity: Default::default(),
+      default_media_type: None,
+      cache_policy: Default::default(),
+      cache_setting: CacheSetting::Use,
+      case_insensitive_file_specifiers: false,
+      on_redirect: None,
+      known_redirects: Default::default(),
+      strip_query_and_fragment: false,
+      resolve_fallback: None,
+      module_extensions: default_module_extensions(),
+      max_modules: None,
+      node_modules_dir: None,
+      lockfile: None,
+      default_package_version: None,
+      jsr_url_provider: None,
+      warn_import_map_bypass: false,
+      types_packages: Default::default(),
+      specifier_vars: Default::default(),
+      vendor_dir: None,
+      json_transform: None,
+      skip_failed_entry_points: false,
+      cdn_normalization: Vec::new(),
+      reporter: None,
+      platform_suffix: None,
+      syntax: Default::default(),
+      index_resolution: false,
+      index_extensions: default_index_extensions(),
+      canonicalize_json: false,
+      cancellation: None,
+      scan_css_assets: false,
+    }
+  }
+}
+
+/// The JavaScript, TypeScript, and JSON file extensions.
+pub fn default_module_extensions() -> HashSet<String> {
+  ["js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts", "json"]
+    .into_iter()
+    .map(|ext| ext.to_string())
+    .collect()
+}
+
+/// The index file extensions tried by default: `ts`, `tsx`, then `js`.
+pub fn default_index_extensions() -> Vec<String> {
+  ["ts", "tsx", "js"]
+    .into_iter()
+    .map(|ext| ext.to_string())
+    .collect()
+}
+
+/// Callback for resolving a specifier relative to a referrer.
+pub type ResolveFallback =
+  dyn Fn(&str, &ModuleSpecifier) -> Option<ModuleSpecifier>;
+
+/// A use of an API on the `Deno` global.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DenoApiUsage {
+  /// Ex. `Deno.readTextFile`
+  pub name: String,
+  pub range: Range,
+}
+
+/// An access of an `import.meta` property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportMetaUsage {
+  /// Ex. `dirname` for `import.meta.dirname`
+  pub name: String,
+  pub range: Range,
+}
+
+/// An export name that's provided by more than one `export *` source
+/// of a barrel module, which makes the name ambiguous.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision {
+  pub name: String,
+  /// The modules that declare the name.
+  pub specifiers: Vec<ModuleSpecifier>,
+}
+
+/// A recoverable issue the parser found in a module, which doesn't
+/// prevent it from being transformed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+  pub range: Range,
+  pub message: String,
+}
+
+/// An exported declaration whose type would need to be inferred from
+/// its implementation in order to emit a declaration file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlowTypeDiagnostic {
+  pub kind: SlowTypeKind,
+  /// Name of the declaration (ex. `MyClass.method`).
+  pub name: String,
+  pub range: Range,
+}
+
+impl SlowTypeDiagnostic {
+  pub fn message(&self) -> String {
+    match self.kind {
+      SlowTypeKind::MissingReturnType => {
+        format!("Missing an explicit return type for {}.", self.name)
+      }
+      SlowTypeKind::MissingParamType => {
+        format!("Missing an explicit type for a parameter of {}.", self.name)
+      }
+      SlowTypeKind::MissingType => {
+        format!("Missing an explicit type for {}.", self.name)
+      }
+      SlowTypeKind::UnsupportedDefaultExport => "The default export must be a declaration, identifier, or literal to have an explicit type.".to_string(),
+    }
+  }
+}
+
+/// How the modules in a [`Cycle`] depend on each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CycleKind {
+  /// Every dependency in the cycle is used at runtime.
+  Runtime,
+  /// Every dependency in the cycle is type-only, which is usually benign.
+  TypeOnly,
+  /// The cycle has both runtime and type-only dependencies.
+  Mixed,
+}
+
+/// Modules that circularly depend on each other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle {
+  /// The modules in dependency order, where the last module depends
+  /// on the first.
+  pub specifiers: Vec<ModuleSpecifier>,
+  pub kind: CycleKind,
+}
+
+/// A remote module or mapped package the graph depends on.
+#[derive(Debug, Clone)]
+pub struct ExternalDependency {
+  /// The specifier after following redirects.
+  pub specifier: ModuleSpecifier,
+  /// What the specifier was mapped to, which is `None` for remote modules
+  /// that will be downloaded to the output.
+  pub mapped: Option<MappedSpecifier>,
+}
+
+/// Wrapper around deno_graph::ModuleGraph.
+pub struct ModuleGraph {
+  graph: deno_graph::ModuleGraph,
+  capturing_analyzer: CapturingModuleAnalyzer,
+  warnings: Vec<String>,
+  strip_query_and_fragment: bool,
+  resolve_fallback: Option<Box<ResolveFallback>>,
+  entry_points: Vec<ModuleSpecifier>,
+  test_entry_points: Vec<ModuleSpecifier>,
+  failed_entry_points: Vec<ModuleSpecifier>,
+  assets: BTreeMap<ModuleSpecifier, Vec<u8>>,
+  cache_stats: CacheStats,
+  /// `node:` specifiers with an entry in the specifier mappings.
+  mapped_node_specifiers: HashSet<ModuleSpecifier>,
+  /// Syntax overrides to parse modules with when re-resolving.
+  syntax: SyntaxOptions,
+  /// Specifiers mapped to another module to what they were mapped to.
+  mapped_modules: BTreeMap<ModuleSpecifier, ModuleSpecifier>,
+  encoding_errors: BTreeMap<ModuleSpecifier, EncodingError>,
+  resolution: ResolutionSettings,
+}
+
+/// The build options needed to resolve the graph's dependencies again.
+#[derive(Default)]
+struct ResolutionSettings {
+  resolver: GraphResolver,
+  specifier_mappings: HashMap<ModuleSpecifier, MappedSpecifier>,
+  types_packages: HashMap<String, String>,
+  module_extensions: HashSet<String>,
+  lockfile: Option<Lockfile>,
+  default_package_version: Option<String>,
+  scan_css_assets: bool,
+}
+
+impl ModuleGraph {
+  /// Builds the graphs concurrently, with a limited number at a time,
+  /// sharing the downloaded remote modules between them. The results are
+  /// in the same order as the options.
+  pub async fn build_many(
+    options_list: Vec<ModuleGraphOptions<'_>>,
+  ) -> Vec<Result<(Self, Specifiers), GraphBuildError>> {
+    const MAX_CONCURRENT_BUILDS: usize = 4;
+
+    let cache = LoaderCache::default();
+    let builds = options_list.into_iter().map(|mut options| {
+      let loader = options
+        .loader
+        .take()
+        .unwrap_or_else(|| get_default_loader(options.on_redirect.take()));
+      options.loader =
+        Some(Rc::new(SharedCacheLoader::new(loader, cache.clone())));
+      Self::build_with_specifiers(options)
+    });
+    futures::stream::iter(builds)
+      .buffered(MAX_CONCURRENT_BUILDS)
+      .collect()
+      .await
+  }
+
+  pub async fn build_with_specifiers(
+    options: ModuleGraphOptions<'_>,
+  ) -> Result<(Self, Specifiers), GraphBuildError> {
+    let loader = options
+      .loader
+      .unwrap_or_else(|| get_default_loader(options.on_redirect));
+    let import_map_resolver = match options.import_map {
+      Some(import_map_url) => Some(
+        ImportMapResolver::load(
+          &import_map_url,
+          &*loader,
+          get_cache_setting(
+            &options.cache_policy,
+            options.cache_setting,
+            CacheSetting::Use,
+            &import_map_url,
+          ),
+        )
+        .await
+        .context("Error loading import map.")?,
+      ),
+      None => None,
+    };
+    let resolver = GraphResolver {
+      import_map: import_map_resolver,
+      node_modules: options
+        .node_modules_dir
+        .map(|dir| NodeModulesResolver::new(&dir, loader.clone())),
+      specifier_vars: options.specifier_vars,
+      cdn_normalization: options.cdn_normalization,
+    };
+    let lockfile = match &options.lockfile {
+      Some(lockfile_url) => Some(
+        load_lockfile(lockfile_url, &*loader)
+          .await
+          .context("Error loading lockfile.")?,
+      ),
+      None => None,
+    };
+    let vendor_dir = match &options.vendor_dir {
+      Some(vendor_dir) => Some(
+        load_vendor_dir(vendor_dir, &*loader)
+          .await
+          .context("Error loading vendor directory.")?,
+      ),
+      None => None,
+    };
+    let locked_jsr_packages = lockfile
+      .as_ref()
+      .map(|lockfile| lockfile.jsr_packages())
+      .unwrap_or_default();
+    let integrity = options
+      .integrity
+      .iter()
+      .map(|(specifier, value)| {
+        Integrity::parse(value)
+          .with_context(|| format!("Invalid integrity for {}", specifier))
+          .map(|integrity| (specifier.clone(), integrity))
+      })
+      .collect::<Result<HashMap<_, _>>>()?;
+    let mut resolution = ResolutionSettings {
+      resolver: Default::default(),
+      specifier_mappings: options.specifier_mappings.clone(),
+      types_packages: options.types_packages.clone(),
+      module_extensions: options.module_extensions.clone(),
+      lockfile: lockfile.clone(),
+      default_package_version: options.default_package_version.clone(),
+      scan_css_assets: options.scan_css_assets,
+    };
+    let loader = match &resolver.node_modules {
+      Some(node_modules) => node_modules.loader(),
+      None => loader,
+    };
+    let mut loader = SourceLoader::new(
+      loader,
+      get_all_specifier_mappers(),
+      options.specifier_mappings,
+      SourceLoaderOptions {
+        max_total_bytes: options.max_total_bytes,
+        integrity,
+        default_media_type: options.default_media_type,
+        cache_policy: options.cache_policy,
+        cache_setting: options.cache_setting,
+        case_insensitive_file_specifiers: options
+          .case_insensitive_file_specifiers,
+        known_redirects: options.known_redirects,
+        module_extensions: options.module_extensions,
+        max_modules: options.max_modules,
+        lockfile,
+        default_package_version: options.default_package_version,
+        vendor_dir,
+        json_transform: options.json_transform,
+        platform_suffix: options.platform_suffix,
+        index_resolution: options.index_resolution,
+        index_extensions: options.index_extensions,
+        canonicalize_json: options.canonicalize_json,
+      },
+    );
+    let source_parser = ScopeAnalysisParser {
+      syntax: options.syntax,
+    };
+    let capturing_analyzer =
+      CapturingModuleAnalyzer::new(Some(Box::new(source_parser)), None);
+    let mut graph = deno_graph::ModuleGraph::new(deno_graph::GraphKind::All);
+    for (req, nv) in locked_jsr_packages {
+      graph.packages.add_nv(req, nv);
+    }
+    let build = graph.build(
+      options
+        .entry_points
+        .iter()
+        .chain(options.test_entry_points.iter())
+        .map(|s| s.to_owned())
+        .collect(),
+      &mut loader,
+      deno_graph::BuildOptions {
+        is_dynamic: false,
+        imports: Default::default(),
+        resolver: Some(&resolver),
+        module_analyzer: Some(&capturing_analyzer),
+        reporter: options.reporter,
+        npm_resolver: None,
+        workspace_members: Default::default(),
+        file_system: None,
+        jsr_url_provider: options.jsr_url_provider,
+        module_parser: Some(&source_parser),
+        executor: Default::default(),
+      },
+    );
+    match options.cancellation {
+      Some(registration) => {
+        if Abortable::new(build, registration).await.is_err() {
+          return Err(GraphBuildError::Cancelled);
+        }
+      }
+      None => {
+        build.await;
+      }
+    }
+
+    if loader.exceeded_max_modules() {
+      return Err(GraphBuildError::Other(anyhow!(
+        "The module graph exceeded the maximum of {} modules ({} modules reached). Check for accidentally broad imports from the entry points:\n{}",
+        options.max_modules.unwrap(),
+        loader.module_count(),
+        format_specifiers_for_message(
+          options
+            .entry_points
+            .iter()
+            .chain(options.test_entry_points.iter())
+            .collect()
+        ),
+      )));
+    }
+
+    let mut entry_points = options.entry_points.clone();
+    let mut test_entry_points = options.test_entry_points.clone();
+    let mut failed_entry_points = Vec::new();
+    if options.skip_failed_entry_points {
+      failed_entry_points = entry_points
+        .iter()
+        .chain(test_entry_points.iter())
+        .filter(|entry_point| has_module_error(&graph, entry_point))
+        .cloned()
+        .collect::<Vec<_>>();
+      // when every entry point failed, surface the errors as usual
+      if failed_entry_points.len()
+        < entry_points.len() + test_entry_points.len()
+      {
+        entry_points.retain(|s| !failed_entry_points.contains(s));
+        test_entry_points.retain(|s| !failed_entry_points.contains(s));
+        if !failed_entry_points.is_empty() {
+          graph = graph.segment(
+            &entry_points
+              .iter()
+              .chain(test_entry_points.iter())
+              .cloned()
+              .collect::<Vec<_>>(),
+          );
+        }
+      } else {
+        failed_entry_points.clear();
+      }
+    }
+
+    let mut errors = graph
+      .module_errors()
+      .map(ModuleLoadError::from_module_error)
+      .collect::<Vec<_>>();
+    // otherwise specifiers with missing variables or of packages with an
+    // invalid package.json would be left unresolved
+    for module in graph.modules().filter_map(|m| m.js()) {
+      for (text, dependency) in &module.dependencies {
+        if let Resolution::Err(err) = &dependency.maybe_code {
+          if text.contains("${") || PackageJsonError::is_resolution_error(err) {
+            errors.push(ModuleLoadError::from_resolution_error(err));
+          }
+        }
+      }
+    }
+    if !errors.is_empty() {
+      return Err(GraphBuildError::Modules(errors));
+    }
+
+    if options.scan_css_assets {
+      loader.load_css_asset_references().await;
+    }
+
+    let mut warnings = loader.take_warnings();
+    if options.warn_import_map_bypass {
+      if let Some(import_map) = &resolver.import_map {
+        warnings.extend(get_import_map_bypass_warnings(&graph, &import_map.0));
+      }
+    }
+    resolution.resolver = resolver;
+
+    let mut graph = Self {
+      graph,
+      capturing_analyzer,
+      warnings,
+      strip_query_and_fragment: options.strip_query_and_fragment,
+      resolve_fallback: options.resolve_fallback,
+      entry_points: entry_points.clone(),
+      test_entry_points: test_entry_points.clone(),
+      failed_entry_points,
+      assets: loader.take_assets(),
+      encoding_errors: loader.take_encoding_errors(),
+      cache_stats: loader.cache_stats(),
+      mapped_node_specifiers: options
+        .specifier_mappings
+        .keys()
+        .filter(|s| s.scheme() == "node")
+        .cloned()
+        .collect(),
+      syntax: options.syntax,
+      mapped_modules: BTreeMap::new(),
+      resolution,
+    };
+
+    let mut loader_specifiers = loader.into_specifiers();
+    graph.mapped_modules = loader_specifiers
+      .mapped_modules
+      .iter()
+      .map(|(k, v)| (k.clone(), v.clone()))
+      .collect();
+    if !graph.failed_entry_points.is_empty() {
+      // only keep the packages used by the remaining entry points
+      loader_specifiers
+        .mapped_packages
+        .retain(|specifier, _| graph.graph.get(specifier).is_some());
+    }
+
+    let not_found_module_mappings = get_not_found_module_mappings(
+      options.specifier_mappings,
+      &loader_specifiers,
+    );
+    if !not_found_module_mappings.is_empty() {
+      return Err(GraphBuildError::UnmappedModules(not_found_module_mappings));
+    }
+
+    let specifiers = get_specifiers(
+      &entry_points,
+      &test_entry_points,
+      loader_specifiers,
+      &options.types_packages,
+      &graph,
+      graph.all_modules(),
+    )?;
+
+    let not_found_package_specifiers =
+      get_not_found_package_mappings(options.specifier_mappings, &specifiers);
+    if !not_found_package_specifiers.is_empty() {
+      return Err(GraphBuildError::UnmappedPackages(
+        not_found_package_specifiers,
+      ));
+    }
+
+    Ok((graph, specifiers))
+  }
+
+  /// Resolves the dependencies of the graph's modules again with the
+  /// provided resolver in place of the import map (ex. after the import
+  /// map changed), reusing the already loaded and parsed sources instead
+  /// of rebuilding the graph. The specifier variables, CDN normalization,
+  /// node_modules directory, and specifier mappings the graph was built
+  /// with still apply. Returns the specifiers of the re-resolved graph.
+  ///
+  /// The assets, mapped modules, encoding errors, and warnings are updated
+  /// to what's used by the new resolution. Errors and leaves the graph
+  /// unchanged when a new resolution refers to a module that wasn't
+  /// loaded, which needs a rebuild instead.
+  pub async fn reresolve(
+    &mut self,
+    resolver: &dyn Resolver,
+  ) -> Result<Specifiers> {
+    let settings = &self.resolution;
+    let resolver = ReplacedImportMapResolver {
+      graph_resolver: &settings.resolver,
+      resolver,
+    };
+    let mut loader = SourceLoader::new(
+      Rc::new(LoadedModulesLoader {
+        graph: self.graph.clone(),
+        assets: self.assets.clone(),
+      }),
+      get_all_specifier_mappers(),
+      &settings.specifier_mappings,
+      // the loaded sources were already transformed and checked
+      SourceLoaderOptions {
+        max_total_bytes: None,
+        integrity: Default::default(),
+        default_media_type: None,
+        cache_policy: Default::default(),
+        cache_setting: CacheSetting::Use,
+        case_insensitive_file_specifiers: false,
+        known_redirects: Default::default(),
+        module_extensions: settings.module_extensions.clone(),
+        max_modules: None,
+        lockfile: settings.lockfile.clone(),
+        default_package_version: settings.default_package_version.clone(),
+        vendor_dir: None,
+        json_transform: None,
+        platform_suffix: None,
+        index_resolution: false,
+        index_extensions: Vec::new(),
+        canonicalize_json: false,
+      },
+    );
+    let source_parser = ScopeAnalysisParser {
+      syntax: self.syntax,
+    };
+    let mut graph = deno_graph::ModuleGraph::new(deno_graph::GraphKind::All);
+    for (req, nv) in self.graph.packages.mappings() {
+      graph.packages.add_nv(req.clone(), nv.clone());
+    }
+    graph
+      .build(
+        self.graph.roots.clone(),
+        &mut loader,
+        deno_graph::BuildOptions {
+          resolver: Some(&resolver),
+          module_analyzer: Some(&self.capturing_analyzer),
+          module_parser: Some(&source_parser),
+          ..Default::default()
+        },
+      )
+      .await;
+    graph.valid().map_err(|err| {
+      anyhow!(
+        "{:#}\n\nThe graph needs to be rebuilt to load modules that weren't already loaded.",
+        err
+      )
+    })?;
+    if settings.scan_css_assets {
+      loader.load_css_asset_references().await;
+    }
+
+    let warnings = loader.take_warnings();
+    let assets = loader.take_assets();
+    let loader_specifiers = loader.into_specifiers();
+    let not_found_module_mappings = get_not_found_module_mappings(
+      &settings.specifier_mappings,
+      &loader_specifiers,
+    );
+    if !not_found_module_mappings.is_empty() {
+      bail!(
+        "{}",
+        GraphBuildError::UnmappedModules(not_found_module_mappings)
+      );
+    }
+    let mapped_modules = loader_specifiers
+      .mapped_modules
+      .iter()
+      .map(|(k, v)| (k.clone(), v.clone()))
+      .collect();
+
+    let previous_graph = std::mem::replace(&mut self.graph, graph);
+    let specifiers = get_specifiers(
+      &self.entry_points,
+      &self.test_entry_points,
+      loader_specifiers,
+      &self.resolution.types_packages,
+      self,
+      self.all_modules(),
+    )
+    .and_then(|specifiers| {
+      let not_found_package_specifiers = get_not_found_package_mappings(
+        &self.resolution.specifier_mappings,
+        &specifiers,
+      );
+      if !not_found_package_specifiers.is_empty() {
+        bail!(
+          "{}",
+          GraphBuildError::UnmappedPackages(not_found_package_specifiers)
+        );
+      }
+      Ok(specifiers)
+    });
+    let specifiers = match specifiers {
+      Ok(specifiers) => specifiers,
+      Err(err) => {
+        self.graph = previous_graph;
+        return Err(err);
+      }
+    };
+
+    self.warnings = warnings;
+    self.assets = assets;
+    self.mapped_modules = mapped_modules;
+    self.mapped_node_specifiers = self
+      .resolution
+      .specifier_mappings
+      .keys()
+      .filter(|s| s.scheme() == "node")
+      .cloned()
+      .collect();
+    let graph = &self.graph;
+    self
+      .encoding_errors
+      .retain(|specifier, _| graph.get(specifier).is_some());
+    Ok(specifiers)
+  }
+
+  /// The entry points that were skipped because they or one of their
+  /// dependencies failed to load when `skip_failed_entry_points` is set.
+  pub fn failed_entry_points(&self) -> &[ModuleSpecifier] {
+    &self.failed_entry_points
+  }
+
+  /// How many of the loaded modules were served from a cache while
+  /// building the graph.
+  pub fn cache_stats(&self) -> CacheStats {
+    self.cache_stats
+  }
+
+  /// Non-fatal warnings that occurred while building the graph.
+  pub fn warnings(&self) -> &[String] {
+    &self.warnings
+  }
+
+  pub fn redirects(&self) -> &BTreeMap<ModuleSpecifier, ModuleSpecifier> {
+    &self.graph.redirects
+  }
+
+  /// The versions that JSR package requirements were resolved to, either
+  /// from the lockfile or by looking up the latest matching version in
+  /// the registry (ex. `jsr:@std/path` to `@std/path@1.0.0`).
+  pub fn jsr_packages(&self) -> Vec<(PackageReq, PackageNv)> {
+    self
+      .graph
+      .packages
+      .mappings()
+      .map(|(req, nv)| (req.clone(), nv.clone()))
+      .collect()
+  }
+
+  pub fn resolve(&self, specifier: &ModuleSpecifier) -> ModuleSpecifier {
+    self.graph.resolve(specifier)
+  }
+
+  pub fn get(&self, specifier: &ModuleSpecifier) -> &Module {
+    self.graph.get(specifier).unwrap_or_else(|| {
+      panic!("dnt bug - Did not find specifier: {}", specifier);
+    })
+  }
+
+  pub fn get_parsed_source(&self, specifier: &ModuleSpecifier) -> ParsedSource {
+    let specifier = self.graph.resolve(specifier);
+    self
+      .capturing_analyzer
+      .get_parsed_source(&specifier)
+      .unwrap_or_else(|| {
+        panic!(
+          "dnt bug - Did not find parsed source for specifier: {}",
+          specifier
+        );
+      })
+  }
+
+  pub fn resolve_dependency(
+    &self,
+    value: &str,
+    referrer: &ModuleSpecifier,
+  ) -> Option<ModuleSpecifier> {
+    let resolved = self
+      .graph
+      .resolve_dependency(value, referrer, /* prefer_types */ false);
+    self.resolve_dependency_fallbacks(value, referrer, resolved)
+  }
+
+  /// Resolves the dependency like `resolve_dependency`, but to its types
+  /// when it has any, such as from a `@deno-types` pragma or an
+  /// `X-TypeScript-Types` header (ex. for generating declaration files).
+  pub fn resolve_dependency_with_types(
+    &self,
+    value: &str,
+    referrer: &ModuleSpecifier,
+  ) -> Option<ModuleSpecifier> {
+    let resolved = self
+      .graph
+      .resolve_dependency(value, referrer, /* prefer_types */ true);
+    self.resolve_dependency_fallbacks(value, referrer, resolved)
+  }
+
+  /// Resolves each of the module's dependencies, in the order they appear,
+  /// the same as `resolve_dependency` would.
+  pub fn resolved_dependencies(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Vec<(String, Option<ModuleSpecifier>)> {
+    let specifier = self.graph.resolve(specifier);
+    let Some(module) = self.graph.get(&specifier).and_then(|m| m.js()) else {
+      return Vec::new();
+    };
+    module
+      .dependencies
+      .iter()
+      .map(|(value, dependency)| {
+        let resolved = self.graph.resolve_dependency_from_dep(
+          dependency, /* prefer_types */ false,
+        );
+        let resolved =
+          self.resolve_dependency_fallbacks(value, &module.specifier, resolved);
+        (value.clone(), resolved)
+      })
+      .collect()
+  }
+
+  fn resolve_dependency_fallbacks(
+    &self,
+    value: &str,
+    referrer: &ModuleSpecifier,
+    resolved: Option<ModuleSpecifier>,
+  ) -> Option<ModuleSpecifier> {
+    resolved
+      .or_else(|| {
+        let value_lower = value.to_lowercase();
+        if value_lower.starts_with("https://")
+          || value_lower.starts_with("http://")
+          || value_lower.starts_with("file://")
+        {
+          ModuleSpecifier::parse(value).ok()
+        } else if value_lower.starts_with("./")
+          || value_lower.starts_with("../")
+        {
+          referrer.join(value).ok()
+        } else {
+          None
+        }
+      })
+      .or_else(|| {
+        let resolve_fallback = self.resolve_fallback.as_ref()?;
+        resolve_fallback(value, referrer)
+      })
+      // builtin node modules are left as-is unless they're mapped
+      .filter(|s| {
+        s.scheme() != "node" || self.mapped_node_specifiers.contains(s)
+      })
+      .map(|mut s| {
+        if self.strip_query_and_fragment {
+          s.set_query(None);
+          s.set_fragment(None);
+        }
+        s
+      })
+  }
+
+  /// Gets the packages that the modules reachable from the entry point are
+  /// mapped to (ex. for the `dependencies` of a `package.json` for only
+  /// that entry point). Packages without a version, like Node built-ins,
+  /// are left out.
+  pub fn external_packages_for(
+    &self,
+    entry: &ModuleSpecifier,
+    specifiers: &Specifiers,
+  ) -> BTreeSet<Dependency> {
+    let mut packages = BTreeSet::new();
+    let mut visited = HashSet::new();
+    let mut pending = vec![self.graph.resolve(entry)];
+    while let Some(specifier) = pending.pop() {
+      if !visited.insert(specifier.clone()) {
+        continue;
+      }
+      let mapped = specifiers
+        .main
+        .mapped
+        .get(&specifier)
+        .or_else(|| specifiers.test.mapped.get(&specifier));
+      if let Some(mapped) = mapped {
+        if let Some(version) = &mapped.version {
+          packages.insert(Dependency {
+            name: mapped.name.clone(),
+            version: version.clone(),
+            peer_dependency: mapped.peer_dependency,
+          });
+        }
+        continue;
+      }
+      let Some(module) = self.graph.get(&specifier).and_then(|m| m.js()) else {
+        continue;
+      };
+      for value in module.dependencies.keys() {
+        pending.extend(
+          [
+            self.resolve_dependency(value, &module.specifier),
+            self.resolve_dependency_with_types(value, &module.specifier),
+          ]
+          .into_iter()
+          .flatten()
+          .map(|s| self.graph.resolve(&s)),
+        );
+      }
+    }
+    packages
+  }
+
+  /// Gets the remote modules and mapped packages the graph depends on,
+  /// along with what each was mapped to, sorted by specifier.
+  pub fn external_dependencies(
+    &self,
+    specifiers: &Specifiers,
+  ) -> Vec<ExternalDependency> {
+    let mut dependencies = BTreeMap::new();
+    for specifier in &specifiers.remote {
+      dependencies.insert(specifier.clone(), None);
+    }
+    for (specifier, mapped) in
+      specifiers.main.mapped.iter().chain(&specifiers.test.mapped)
+    {
+      dependencies.insert(
+        specifier.clone(),
+        Some(MappedSpecifier::Package(mapped.clone())),
+      );
+    }
+    for (specifier, redirect) in &self.mapped_modules {
+      if specifier.scheme() != "file" {
+        dependencies.insert(
+          specifier.clone(),
+          Some(MappedSpecifier::Module(redirect.clone())),
+        );
+      }
+    }
+    dependencies
+      .into_iter()
+      .map(|(specifier, mapped)| ExternalDependency { specifier, mapped })
+      .collect()
+  }
+
+  pub fn all_modules(&self) -> impl Iterator<Item = &Module> {
+    self.graph.modules()
+  }
+
+  /// Gets the circular dependencies between modules in the graph.
+  pub fn find_cycles(&self) -> Vec<Vec<ModuleSpecifier>> {
+    self
+      .find_cycles_by_kind()
+      .into_iter()
+      .map(|cycle| cycle.specifiers)
+      .collect()
+  }
+
+  /// Gets the circular dependencies between modules in the graph along
+  /// with whether the dependencies that form them are used at runtime.
+  ///
+  /// One cycle is reported for each dependency that closes a cycle while
+  /// walking the graph in sorted order, so the same modules may appear in
+  /// more than one cycle.
+  pub fn find_cycles_by_kind(&self) -> Vec<Cycle> {
+    // module to its dependencies and whether each is used at runtime
+    let js_specifiers = self
+      .graph
+      .modules()
+      .filter_map(|m| m.js())
+      .map(|m| &m.specifier)
+      .collect::<HashSet<_>>();
+    let mut edges: BTreeMap<&ModuleSpecifier, BTreeMap<ModuleSpecifier, bool>> =
+      BTreeMap::new();
+    for module in self.graph.modules().filter_map(|m| m.js()) {
+      let module_edges = edges.entry(&module.specifier).or_default();
+      for dependency in module.dependencies.values() {
+        let Some(specifier) = dependency.get_code().or(dependency.get_type())
+        else {
+          continue;
+        };
+        let specifier = self.graph.resolve(specifier);
+        if !js_specifiers.contains(&specifier) {
+          continue;
+        }
+        let is_runtime = dependency.get_code().is_some()
+          && dependency.imports.iter().any(|i| i.kind.is_runtime());
+        *module_edges.entry(specifier).or_default() |= is_runtime;
+      }
+    }
+
+    struct Context<'a> {
+      edges: &'a BTreeMap<&'a ModuleSpecifier, BTreeMap<ModuleSpecifier, bool>>,
+      visited: HashSet<&'a ModuleSpecifier>,
+      stack: Vec<(&'a ModuleSpecifier, bool)>,
+      cycles: Vec<Cycle>,
+    }
+
+    fn visit<'a>(context: &mut Context<'a>, specifier: &'a ModuleSpecifier) {
+      context.visited.insert(specifier);
+      let Some(module_edges) = context.edges.get(specifier) else {
+        return;
+      };
+      for (dependency, is_runtime) in module_edges {
+        let stack_index =
+          context.stack.iter().position(|(s, _)| *s == dependency);
+        if let Some(index) = stack_index {
+          // the first entry's edge leads into the cycle, so skip it
+          let mut edge_kinds = context.stack[index + 1..]
+            .iter()
+            .map(|(_, is_runtime)| *is_runtime)
+            .collect::<Vec<_>>();
+          edge_kinds.push(*is_runtime);
+          let kind = if edge_kinds.iter().all(|k| *k) {
+            CycleKind::Runtime
+          } else if edge_kinds.iter().all(|k| !*k) {
+            CycleKind::TypeOnly
+          } else {
+            CycleKind::Mixed
+          };
+          context.cycles.push(Cycle {
+            specifiers: context.stack[index..]
+              .iter()
+              .map(|(s, _)| (*s).clone())
+              .collect(),
+            kind,
+          });
+        } else if !context.visited.contains(dependency) {
+          context.stack.push((dependency, *is_runtime));
+          visit(context, dependency);
+          context.stack.pop();
+        }
+      }
+    }
+
+    let mut context = Context {
+      edges: &edges,
+      visited: HashSet::new(),
+      stack: Vec::new(),
+      cycles: Vec::new(),
+    };
+    for specifier in edges.keys() {
+      if !context.visited.contains(specifier) {
+        context.stack.push((specifier, true));
+        visit(&mut context, specifier);
+        context.stack.pop();
+      }
+    }
+    context.cycles
+  }
+
+  /// Gets the number of modules of each media type. External, npm, and
+  /// node modules are not included since they don't have one.
+  pub fn media_type_counts(&self) -> BTreeMap<MediaType, usize> {
+    let mut counts = BTreeMap::new();
+    for module in self.graph.modules() {
+      let media_type = match module {
+        Module::Js(module) => module.media_type,
+        Module::Json(module) => module.media_type,
+        Module::Npm(_) | Module::Node(_) | Module::External(_) => continue,
+      };
+      *counts.entry(media_type).or_default() += 1;
+    }
+    counts
+  }
+
+  /// Assigns each module in the graph an id based on the sorted order
+  /// of the specifiers, so the ids are stable for the same modules.
+  pub fn module_ids(&self) -> BTreeMap<ModuleSpecifier, u32> {
+    let specifiers = self
+      .graph
+      .modules()
+      .map(|module| module.specifier().clone())
+      .collect::<BTreeSet<_>>();
+    specifiers
+      .into_iter()
+      .enumerate()
+      .map(|(id, specifier)| (specifier, id as u32))
+      .collect()
+  }
+
+  /// Gets a view of the modules reachable from the entry points.
+  pub fn production_view(&self) -> ModuleGraphView<'_> {
+    ModuleGraphView::new(self, &self.entry_points)
+  }
+
+  /// Gets a view of the modules reachable from the test entry points.
+  pub fn test_view(&self) -> ModuleGraphView<'_> {
+    ModuleGraphView::new(self, &self.test_entry_points)
+  }
+
+  /// Gets the range of the string literal, including its quotes, of the
+  /// first import of `dep_value` in the module.
+  pub fn dependency_range(
+    &self,
+    module: &ModuleSpecifier,
+    dep_value: &str,
+  ) -> Option<Range> {
+    let module = self.graph.get(module)?.js()?;
+    let dependency = module.dependencies.get(dep_value)?;
+    dependency
+      .imports
+      .first()
+      .map(|import| import.range.clone())
+  }
+
+  /// Gets the specifiers referenced by string literal `import.meta.resolve(...)`
+  /// calls in the provided module.
+  pub fn meta_resolve_targets(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Vec<ModuleSpecifier> {
+    let Some(parsed_source) = self.maybe_parsed_source(specifier) else {
+      return Vec::new();
+    };
+    let referrer = parsed_source.specifier();
+    parsed_source
+      .with_view(get_import_meta_resolve_specifiers)
+      .into_iter()
+      .filter_map(|value| self.resolve_dependency(&value, referrer))
+      .collect()
+  }
+
+  /// Gets the contents of the files that were loaded as assets because
+  /// their extension isn't one of the module extensions.
+  pub fn assets(&self) -> &BTreeMap<ModuleSpecifier, Vec<u8>> {
+    &self.assets
+  }
+
+  /// Gets the specifiers of the files a CSS asset references via
+  /// `@import` and `url()`.
+  pub fn css_asset_references(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Vec<ModuleSpecifier> {
+    let specifier = self.graph.resolve(specifier);
+    match self.assets.get(&specifier) {
+      Some(bytes) => get_css_asset_references(&specifier, bytes),
+      None => Vec::new(),
+    }
+  }
+
+  /// Gets the modules whose source wasn't valid UTF-8 (ex. a file decoded
+  /// from UTF-16 or a remote module served with another charset), along
+  /// with where the first invalid byte is. Assets aren't checked.
+  pub fn validate_encodings(&self) -> Vec<(ModuleSpecifier, EncodingError)> {
+    self
+      .encoding_errors
+      .iter()
+      .filter(|(specifier, _)| self.graph.get(specifier).is_some())
+      .map(|(specifier, err)| (specifier.clone(), *err))
+      .collect()
+  }
+
+  /// Gets the imports and exports of a Wasm module that was loaded as an
+  /// asset, which are needed to generate code that instantiates it.
+  pub fn wasm_module_info(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Option<WasmModuleInfo> {
+    let bytes = self.assets.get(&self.graph.resolve(specifier))?;
+    get_wasm_module_info(bytes)
+  }
+
+  /// Gets the specifiers of assets fetched relative to the module via
+  /// `fetch(new URL("<path>", import.meta.url))`.
+  pub fn fetched_assets(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Vec<ModuleSpecifier> {
+    let Some(parsed_source) = self.maybe_parsed_source(specifier) else {
+      return Vec::new();
+    };
+    let referrer = parsed_source.specifier();
+    parsed_source
+      .with_view(|program| {
+        get_fetched_asset_specifiers(
+          program,
+          parsed_source.unresolved_context(),
+        )
+      })
+      .into_iter()
+      .filter_map(|value| referrer.join(&value).ok())
+      .collect()
+  }
+
+  /// Gets the uses of `Deno.*` APIs in the module, excluding any where
+  /// `Deno` is shadowed by a local declaration.
+  pub fn deno_global_usages(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Vec<DenoApiUsage> {
+    let Some(parsed_source) = self.maybe_parsed_source(specifier) else {
+      return Vec::new();
+    };
+    parsed_source
+      .with_view(|program| {
+        get_deno_global_usages(program, parsed_source.unresolved_context())
+      })
+      .into_iter()
+      .map(|(name, range)| DenoApiUsage {
+        name,
+        range: to_range(&parsed_source, range),
+      })
+      .collect()
+  }
+
+  /// Gets the accesses of `import.meta` properties in the module, which
+  /// is useful for finding the ones that aren't supported in Node such
+  /// as `import.meta.dirname` and `import.meta.filename`.
+  pub fn import_meta_usages(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Vec<ImportMetaUsage> {
+    let Some(parsed_source) = self.maybe_parsed_source(specifier) else {
+      return Vec::new();
+    };
+    parsed_source
+      .with_view(get_import_meta_usages)
+      .into_iter()
+      .map(|(name, range)| ImportMetaUsage {
+        name,
+        range: to_range(&parsed_source, range),
+      })
+      .collect()
+  }
+
+  /// Gets the ranges of references to the provided globals in the module,
+  /// keyed by global name. References shadowed by a local declaration
+  /// and references in type positions are excluded.
+  pub fn global_usages(
+    &self,
+    specifier: &ModuleSpecifier,
+    globals: &[&str],
+  ) -> HashMap<String, Vec<Range>> {
+    let mut result: HashMap<String, Vec<Range>> = HashMap::new();
+    let Some(parsed_source) = self.maybe_parsed_source(specifier) else {
+      return result;
+    };
+    let usages = parsed_source.with_view(|program| {
+      get_global_usages(program, parsed_source.unresolved_context(), globals)
+    });
+    for (name, range) in usages {
+      result
+        .entry(name)
+        .or_default()
+        .push(to_range(&parsed_source, range));
+    }
+    result
+  }
+
+  /// Gets the text of the module with the specifiers of its imports,
+  /// exports, and dynamic imports replaced by the mapper. Specifiers the
+  /// mapper returns `None` for are left as-is.
+  ///
+  /// Returns `None` when the module was not parsed (ex. a JSON module).
+  pub fn rewrite_imports(
+    &self,
+    specifier: &ModuleSpecifier,
+    mapper: impl Fn(&str) -> Option<String>,
+  ) -> Option<String> {
+    let parsed_source = self.maybe_parsed_source(specifier)?;
+    let text_changes = parsed_source
+      .with_view(|program| get_module_specifier_text_changes(program, &mapper));
+    Some(apply_text_changes(
+      parsed_source.text_info().text_str(),
+      text_changes,
+    ))
+  }
+
+  /// Gets the ranges of dynamic imports in the module whose specifier
+  /// isn't a string literal and so can't be statically resolved.
+  pub fn unanalyzable_dynamic_imports(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Vec<Range> {
+    let Some(parsed_source) = self.maybe_parsed_source(specifier) else {
+      return Vec::new();
+    };
+    parsed_source
+      .with_view(get_unanalyzable_dynamic_imports)
+      .into_iter()
+      .map(|range| to_range(&parsed_source, range))
+      .collect()
+  }
+
+  /// Gets the names and ranges of imported bindings that are never
+  /// referenced in the module. A reference in a type position counts
+  /// as a use.
+  pub fn unused_imports(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Vec<(String, Range)> {
+    let Some(parsed_source) = self.maybe_parsed_source(specifier) else {
+      return Vec::new();
+    };
+    parsed_source
+      .with_view(get_unused_imports)
+      .into_iter()
+      .map(|(name, range)| (name, to_range(&parsed_source, range)))
+      .collect()
+  }
+
+  /// Gets the exported declarations of the TypeScript modules in the graph
+  /// that are missing explicit types, which makes the types of the
+  /// emitted declaration files depend on inference.
+  pub fn slow_type_diagnostics(&self) -> Vec<SlowTypeDiagnostic> {
+    let mut diagnostics = Vec::new();
+    for module in self.graph.modules().filter_map(|m| m.js()) {
+      if !matches!(
+        module.media_type,
+        MediaType::TypeScript
+          | MediaType::Mts
+          | MediaType::Cts
+          | MediaType::Tsx
+      ) {
+        continue;
+      }
+      let Some(parsed_source) = self.maybe_parsed_source(&module.specifier)
+      else {
+        continue;
+      };
+      diagnostics.extend(
+        parsed_source.with_view(get_slow_types).into_iter().map(
+          |(kind, name, range)| SlowTypeDiagnostic {
+            kind,
+            name,
+            range: to_range(&parsed_source, range),
+          },
+        ),
+      );
+    }
+    diagnostics
+  }
+
+  /// Gets the libs referenced by `/// <reference lib="..." />` directives
+  /// across all modules (ex. `dom`), which the output's `tsconfig.json`
+  /// needs in its `lib` array.
+  pub fn referenced_libs(&self) -> HashSet<String> {
+    let mut libs = HashSet::new();
+    for module in self.graph.modules().filter_map(|m| m.js()) {
+      if let Some(parsed_source) = self.maybe_parsed_source(&module.specifier) {
+        libs.extend(parsed_source.with_view(get_referenced_libs));
+      }
+    }
+    libs
+  }
+
+  /// Gets the non-fatal diagnostics reported while parsing each module.
+  /// Modules without any are left out.
+  pub fn parse_diagnostics(
+    &self,
+  ) -> BTreeMap<ModuleSpecifier, Vec<ParseWarning>> {
+    let mut diagnostics = BTreeMap::new();
+    for module in self.graph.modules().filter_map(|m| m.js()) {
+      let Some(parsed_source) = self.maybe_parsed_source(&module.specifier)
+      else {
+        continue;
+      };
+      if parsed_source.diagnostics().is_empty() {
+        continue;
+      }
+      diagnostics.insert(
+        module.specifier.clone(),
+        parsed_source
+          .diagnostics()
+          .iter()
+          .map(|diagnostic| ParseWarning {
+            range: to_range(&parsed_source, diagnostic.range),
+            message: diagnostic.kind.msg().to_string(),
+          })
+          .collect(),
+      );
+    }
+    diagnostics
+  }
+
+  /// Gets the string literal specifiers of any calls to the global
+  /// `require` function in the module along with the range of the
+  /// string literal.
+  pub fn require_calls(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Vec<(String, Range)> {
+    let Some(parsed_source) = self.maybe_parsed_source(specifier) else {
+      return Vec::new();
+    };
+    parsed_source
+      .with_view(|program| {
+        get_require_calls(program, parsed_source.unresolved_context())
+      })
+      .into_iter()
+      .filter_map(|call| {
+        let range = to_range(&parsed_source, call.range);
+        call.specifier.map(|specifier| (specifier, range))
+      })
+      .collect()
+  }
+
+  /// Gets the ranges of any calls to the global `require` function in
+  /// the module whose argument isn't a string literal.
+  pub fn unanalyzable_require_calls(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Vec<Range> {
+    let Some(parsed_source) = self.maybe_parsed_source(specifier) else {
+      return Vec::new();
+    };
+    parsed_source
+      .with_view(|program| {
+        get_require_calls(program, parsed_source.unresolved_context())
+      })
+      .into_iter()
+      .filter(|call| call.specifier.is_none())
+      .map(|call| to_range(&parsed_source, call.range))
+      .collect()
+  }
+
+  /// Gets the 1-indexed line and column of the byte offset in the source
+  /// text of the module.
+  pub fn position_at(
+    &self,
+    specifier: &ModuleSpecifier,
+    offset: usize,
+  ) -> Option<(usize, usize)> {
+    let parsed_source = self.maybe_parsed_source(specifier)?;
+    let text_info = parsed_source.text_info();
+    if !text_info.text_str().is_char_boundary(offset) {
+      return None;
+    }
+    let index =
+      text_info.line_and_column_index(text_info.range().start + offset);
+    Some((index.line_index + 1, index.column_index + 1))
+  }
+
+  /// Gets the text of the module with the prelude inserted after any
+  /// shebang and leading comments.
+  ///
+  /// Returns `None` when the module was not parsed (ex. a JSON module).
+  pub fn with_prelude(
+    &self,
+    specifier: &ModuleSpecifier,
+    prelude: &str,
+  ) -> Option<String> {
+    let parsed_source = self.maybe_parsed_source(specifier)?;
+    let text_change = parsed_source.with_view(|program| {
+      text_change_for_prepend_statement_to_text(program, prelude)
+    });
+    Some(apply_text_changes(
+      parsed_source.text_info().text_str(),
+      vec![text_change],
+    ))
+  }
+
+  /// Gets if the module has top level code that may have side effects
+  /// when evaluated (ex. a top level call or assignment), as opposed to
+  /// only having imports, exports, and declarations.
+  ///
+  /// Returns `false` when the module is not in the graph.
+  pub fn has_top_level_side_effects(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> bool {
+    let Some(parsed_source) = self.maybe_parsed_source(specifier) else {
+      return false;
+    };
+    parsed_source.with_view(has_top_level_side_effects)
+  }
+
+  /// Gets the specifiers of the imports, exports, and dynamic imports of
+  /// each module that has any, as written in the source and before any
+  /// resolution.
+  pub fn raw_import_specifiers(
+    &self,
+  ) -> BTreeMap<ModuleSpecifier, Vec<String>> {
+    let mut result = BTreeMap::new();
+    for module in self.graph.modules() {
+      let Some(parsed_source) = self
+        .capturing_analyzer
+        .get_parsed_source(module.specifier())
+      else {
+        continue;
+      };
+      let text = parsed_source.text_info().text_str();
+      // the text changes provide the ranges of the specifier text
+      let specifiers = parsed_source
+        .with_view(|program| {
+          get_module_specifier_text_changes(program, &|value| {
+            Some(value.to_string())
+          })
+        })
+        .into_iter()
+        .map(|change| text[change.range].to_string())
+        .collect::<Vec<_>>();
+      if !specifiers.is_empty() {
+        result.insert(module.specifier().clone(), specifiers);
+      }
+    }
+    result
+  }
+
+  /// Gets the TypeScript-only constructs used in the module, which
+  /// indicates whether it needs to be transpiled.
+  ///
+  /// Returns an empty set when the module is not in the graph.
+  pub fn uses_typescript_features(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> TsFeatureSet {
+    let Some(parsed_source) = self.maybe_parsed_source(specifier) else {
+      return TsFeatureSet::default();
+    };
+    parsed_source.with_view(get_typescript_features)
+  }
+
+  /// Gets the names that are re-exported from more than one module via
+  /// the `export *` declarations of the barrel module. Names the barrel
+  /// module exports itself take precedence, so they don't collide.
+  pub fn barrel_collisions(
+    &self,
+    barrel_specifier: &ModuleSpecifier,
+  ) -> Vec<Collision> {
+    let Some(parsed_source) = self.maybe_parsed_source(barrel_specifier) else {
+      return Vec::new();
+    };
+    let exports = parsed_source.with_view(get_module_exports);
+    let mut star_exports = BTreeMap::new();
+    let mut visited = HashSet::from([parsed_source.specifier().clone()]);
+    for star_specifier in &exports.star_specifiers {
+      if let Some(specifier) =
+        self.resolve_dependency(star_specifier, parsed_source.specifier())
+      {
+        self.collect_star_exports(&specifier, &mut star_exports, &mut visited);
+      }
+    }
+    star_exports
+      .into_iter()
+      .filter(|(name, specifiers)| {
+        specifiers.len() > 1 && !exports.names.contains(name)
+      })
+      .map(|(name, specifiers)| Collision {
+        name,
+        specifiers: specifiers.into_iter().collect(),
+      })
+      .collect()
+  }
+
+  /// Collects the modules that declare each name that an `export *` of
+  /// the provided module would re-export, skipping modules in `visited`.
+  fn collect_star_exports(
+    &self,
+    specifier: &ModuleSpecifier,
+    star_exports: &mut BTreeMap<String, BTreeSet<ModuleSpecifier>>,
+    visited: &mut HashSet<ModuleSpecifier>,
+  ) {
+    let Some(parsed_source) = self.maybe_parsed_source(specifier) else {
+      return;
+    };
+    let specifier = parsed_source.specifier();
+    if !visited.insert(specifier.clone()) {
+      return;
+    }
+    let exports = parsed_source.with_view(get_module_exports);
+    for name in exports.names.iter() {
+      // the default export is not included in star exports
+      if name != "default" {
+        star_exports
+          .entry(name.clone())
+          .or_default()
+          .insert(specifier.clone());
+      }
+    }
+    let mut nested_exports = BTreeMap::new();
+    for star_specifier in &exports.star_specifiers {
+      if let Some(nested_specifier) =
+        self.resolve_dependency(star_specifier, specifier)
+      {
+        self.collect_star_exports(
+          &nested_specifier,
+          &mut nested_exports,
+          visited,
+        );
+      }
+    }
+    for (name, specifiers) in nested_exports {
+      if !exports.names.contains(&name) {
+        star_exports.entry(name).or_default().extend(specifiers);
+      }
+    }
+    // only guard against cycles so modules reachable via multiple
+    // paths are still analyzed for each path
+    visited.remove(specifier);
+  }
+
+  fn maybe_parsed_source(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Option<ParsedSource> {
+    let specifier = self.graph.resolve(specifier);
+    self.capturing_analyzer.get_parsed_source(&specifier)
+  }
+}
+
+/// Filtered view of the modules in a `ModuleGraph` that are reachable
+/// from a subset of its roots.
+pub struct ModuleGraphView<'a> {
+  graph: &'a ModuleGraph,
+  specifiers: HashSet<ModuleSpecifier>,
+}
+
+impl<'a> ModuleGraphView<'a> {
+  fn new(graph: &'a ModuleGraph, roots: &[ModuleSpecifier]) -> Self {
+    let specifiers = graph
+      .graph
+      .walk(
+        roots,
+        deno_graph::WalkOptions {
+          follow_dynamic: true,
+          follow_type_only: true,
+          check_js: true,
+        },
+      )
+      .filter_map(|(_, entry)| match entry {
+        ModuleEntryRef::Module(module) => Some(module.specifier().clone()),
+        ModuleEntryRef::Err(_) | ModuleEntryRef::Redirect(_) => None,
+      })
+      .collect();
+    Self { graph, specifiers }
+  }
+
+  pub fn contains(&self, specifier: &ModuleSpecifier) -> bool {
+    self.specifiers.contains(&self.graph.resolve(specifier))
+  }
+
+  pub fn get(&self, specifier: &ModuleSpecifier) -> Option<&'a Module> {
+    if self.contains(specifier) {
+      self.graph.graph.get(specifier)
+    } else {
+      None
+    }
+  }
+
+  pub fn modules(&self) -> impl Iterator<Item = &'a Module> + '_ {
+    self
+      .graph
+      .all_modules()
+      .filter(|module| self.specifiers.contains(module.specifier()))
+  }
+}
+
+fn format_specifiers_for_message(
+  mut specifiers: Vec<&ModuleSpecifier>,
+) -> String {
+  specifiers.sort();
+  specifiers
+    .into_iter()
+    .map(|s| format!("  * {}", s))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+fn to_range(parsed_source: &ParsedSource, range: SourceRange) -> Range {
+  let text_info = parsed_source.text_info();
+  Range {
+    specifier: parsed_source.specifier().clone(),
+    start: Position::from_source_pos(range.start, text_info),
+    end: Position::from_source_pos(range.end, text_info),
+  }
+}
+
+/// Gets if a module reachable from the entry point failed to load.
+fn has_module_error(
+  graph: &deno_graph::ModuleGraph,
+  entry_point: &ModuleSpecifier,
+) -> bool {
+  graph
+    .walk(
+      std::slice::from_ref(entry_point),
+      deno_graph::WalkOptions {
+        follow_dynamic: true,
+        follow_type_only: true,
+        check_js: true,
+      },
+    )
+    .any(|(_, entry)| matches!(entry, ModuleEntryRef::Err(_)))
+}
+
+fn get_default_loader(
+  on_redirect: Option<Box<RedirectCallback>>,
+) -> Rc<dyn Loader> {
+  #[cfg(feature = "tokio-loader")]
+  return Rc::new(match on_redirect {
+    Some(on_redirect) => crate::loader::DefaultLoader::new()
+      .with_on_redirect(Rc::from(on_redirect)),
+    None => crate::loader::DefaultLoader::new(),
+  });
+  #[cfg(not(feature = "tokio-loader"))]
+  {
+    let _ = on_redirect;
+    panic!("You must provide a loader or use the 'tokio-loader' feature.")
+  }
+}
+
+async fn load_lockfile(
+  lockfile_url: &ModuleSpecifier,
+  loader: &dyn Loader,
+) -> Result<Lockfile> {
+  let response = loader
+    .load(lockfile_url.clone(), CacheSetting::Use, None)
+    .await?
+    .ok_or_else(|| anyhow!("Could not find {}", lockfile_url))?;
+  Lockfile::parse(&String::from_utf8(response.content)?)
+}
+
+async fn load_vendor_dir(
+  vendor_dir: &ModuleSpecifier,
+  loader: &dyn Loader,
+) -> Result<VendorDir> {
+  let mut vendor_dir = vendor_dir.clone();
+  if !vendor_dir.path().ends_with('/') {
+    vendor_dir.set_path(&format!("{}/", vendor_dir.path()));
+  }
+  let import_map_url = vendor_dir.join("import_map.json")?;
+  let response = loader
+    .load(import_map_url.clone(), CacheSetting::Use, None)
+    .await?
+    .ok_or_else(|| anyhow!("Could not find {}", import_map_url))?;
+  VendorDir::parse(import_map_url, &String::from_utf8(response.content)?)
+}
+
+#[derive(Debug)]
+struct ImportMapResolver(import_map::ImportMap);
+
+impl ImportMapResolver {
+  pub async fn load(
+    import_map_url: &ModuleSpecifier,
+    loader: &dyn Loader,
+    cache_setting: CacheSetting,
+  ) -> Result<Self> {
+    let response = loader
+      .load(import_map_url.clone(), cache_setting, None)
+      .await?
+      .ok_or_else(|| anyhow!("Could not find {}", import_map_url))?;
+    let value = jsonc_parser::parse_to_serde_value(
+      &String::from_utf8(response.content)?,
+      &jsonc_parser::ParseOptions {
+        allow_comments: true,
+        allow_loose_object_property_names: true,
+        allow_trailing_commas: true,
+      },
+    )
+    .with_context(|| {
+      format!(
+        concat!(
+          "Error parsing {}. Import maps are parsed as JSONC, which supports ",
+          "comments, trailing commas, single quoted strings, and unquoted ",
+          "property names, but not the rest of JSON5."
+        ),
+        import_map_url
+      )
+    })?
+    .unwrap_or_else(|| serde_json::Value::Object(Default::default()));
+    let result = import_map::parse_from_value_with_options(
+      import_map_url.clone(),
+      value,
+      ImportMapOptions {
+        address_hook: None,
+        expand_imports: true,
+      },
+    )?;
+    // if !result.diagnostics.is_empty() {
+    //   todo: surface diagnostics maybe? It seems like this should not be hard error according to import map spec
+    //   bail!("Import map diagnostics:\n{}", result.diagnostics.into_iter().map(|d| format!("  - {}", d)).collect::<Vec<_>>().join("\n"));
+    //}
+    Ok(ImportMapResolver(result.import_map))
+  }
+}
+
+impl deno_graph::source::Resolver for ImportMapResolver {
+  fn resolve(
+    &self,
+    specifier: &str,
+    referrer_range: &Range,
+    _mode: ResolutionMode,
+  ) -> Result<ModuleSpecifier, ResolveError> {
+    self
+      .0
+      .resolve(specifier, &referrer_range.specifier)
+      .map_err(|err| ResolveError::Other(err.into()))
+  }
+}
+
+/// Replaces the `${NAME}` placeholders in the specifier, erroring with
+/// the names of any that weren't provided.
+fn substitute_specifier_vars<'a>(
+  specifier: &'a str,
+  vars: &HashMap<String, String>,
+) -> Result<Cow<'a, str>> {
+  if !specifier.contains("${") {
+    return Ok(Cow::Borrowed(specifier));
+  }
+  let mut result = String::new();
+  let mut missing_vars = Vec::new();
+  let mut remaining = specifier;
+  while let Some(start) = remaining.find("${") {
+    let Some(len) = remaining[start..].find('}') else {
+      break;
+    };
+    let name = &remaining[start + 2..start + len];
+    result.push_str(&remaining[..start]);
+    match vars.get(name) {
+      Some(value) => result.push_str(value),
+      None => missing_vars.push(name),
+    }
+    remaining = &remaining[start + len + 1..];
+  }
+  result.push_str(remaining);
+  if !missing_vars.is_empty() {
+    bail!(
+      "Missing specifier variable{} {} for {}",
+      if missing_vars.len() == 1 { "" } else { "s" },
+      missing_vars.join(", "),
+      specifier,
+    );
+  }
+  Ok(Cow::Owned(result))
+}
+
+/// Gets the specifiers mapped to a module that no module resolved to.
+fn get_not_found_module_mappings(
+  specifier_mappings: &HashMap<ModuleSpecifier, MappedSpecifier>,
+  loader_specifiers: &LoaderSpecifiers,
+) -> Vec<ModuleSpecifier> {
+  specifier_mappings
+    .iter()
+    .filter_map(|(k, v)| match v {
+      MappedSpecifier::Package(_) => None,
+      MappedSpecifier::Module(_) => Some(k),
+    })
+    .filter(|s| !loader_specifiers.mapped_modules.contains_key(s))
+    .cloned()
+    .collect()
+}
+
+/// Gets the specifiers mapped to a package that aren't used.
+fn get_not_found_package_mappings(
+  specifier_mappings: &HashMap<ModuleSpecifier, MappedSpecifier>,
+  specifiers: &Specifiers,
+) -> Vec<ModuleSpecifier> {
+  specifier_mappings
+    .iter()
+    .filter_map(|(k, v)| match v {
+      MappedSpecifier::Package(_) => Some(k),
+      MappedSpecifier::Module(_) => None,
+    })
+    .filter(|s| !specifiers.has_mapped(s))
+    .cloned()
+    .collect()
+}
+
+/// Gets warnings for imports of absolute URLs that the import map
+/// provides a bare specifier for.
+fn get_import_map_bypass_warnings(
+  graph: &deno_graph::ModuleGraph,
+  import_map: &import_map::ImportMap,
+) -> Vec<String> {
+  let mut warnings = Vec::new();
+  for module in graph.modules().filter_map(|m| m.js()) {
+    for (text, dependency) in &module.dependencies {
+      let Ok(url) = ModuleSpecifier::parse(text) else {
+        continue;
+      };
+      let Some(bare_specifier) = import_map
+        .lookup(&url, &module.specifier)
+        .filter(|key| is_bare_specifier(key))
+      else {
+        continue;
+      };
+      let mut warning = format!(
+        "Import of {} bypasses the import map. Use \"{}\" instead.",
+        text, bare_specifier,
+      );
+      if let Some(import) = dependency.imports.first() {
+        write!(warning, "\n    at {}", import.range).unwrap();
+      }
+      warnings.push(warning);
+    }
+  }
+  warnings
+}
+
+fn is_bare_specifier(specifier: &str) -> bool {
+  !specifier.starts_with("./")
+    && !specifier.starts_with("../")
+    && !specifier.starts_with('/')
+    && ModuleSpecifier::parse(specifier).is_err()
+}
+
+/// Rewrites a remote specifier with the first matching CDN normalization
+/// pattern. Relative specifiers in remote modules are joined with the
+/// referrer first so they're normalized as well.
+fn normalize_cdn_specifier<'a>(
+  specifier: &'a str,
+  referrer: &ModuleSpecifier,
+  cdn_normalization: &[(Regex, String)],
+) -> Cow<'a, str> {
+  if cdn_normalization.is_empty() {
+    return Cow::Borrowed(specifier);
+  }
+  let url = match ModuleSpecifier::parse(specifier) {
+    Ok(url) => url,
+    Err(_)
+      if matches!(referrer.scheme(), "http" | "https")
+        && (specifier.starts_with("./")
+          || specifier.starts_with("../")
+          || specifier.starts_with('/')) =>
+    {
+      match referrer.join(specifier) {
+        Ok(url) => url,
+        Err(_) => return Cow::Borrowed(specifier),
+      }
+    }
+    Err(_) => return Cow::Borrowed(specifier),
+  };
+  if !matches!(url.scheme(), "http" | "https") {
+    return Cow::Borrowed(specifier);
+  }
+  cdn_normalization
+    .iter()
+    .find(|(pattern, _)| pattern.is_match(url.as_str()))
+    .map(|(pattern, replacement)| {
+      Cow::Owned(pattern.replace(url.as_str(), replacement).into_owned())
+    })
+    .unwrap_or(Cow::Borrowed(specifier))
+}
+
+/// Serves the sources of an already built graph, following its redirects,
+/// so its dependencies can be resolved again without loading anything.
+struct LoadedModulesLoader {
+  graph: deno_graph::ModuleGraph,
+  assets: BTreeMap<ModuleSpecifier, Vec<u8>>,
+}
+
+impl Loader for LoadedModulesLoader {
+  fn load(
+    &self,
+    specifier: ModuleSpecifier,
+    _cache_setting: CacheSetting,
+    _maybe_checksum: Option<LoaderChecksum>,
+  ) -> Pin<Box<dyn Future<Output = Result<Option<LoadResponse>>> + 'static>> {
+    let specifier = self.graph.resolve(&specifier);
+    let mut headers = HashMap::new();
+    let (content, media_type) = match self.graph.get(&specifier) {
+      Some(Module::Js(module)) => {
+        if let Some(types_dependency) = &module.maybe_types_dependency {
+          headers.insert(
+            "x-typescript-types".to_string(),
+            types_dependency.specifier.clone(),
+          );
+        }
+        (module.source.as_bytes().to_vec(), module.media_type)
+      }
+      Some(Module::Json(module)) => {
+        (module.source.as_bytes().to_vec(), module.media_type)
+      }
+      _ => {
+        let response =
+          self.assets.get(&specifier).map(|content| LoadResponse {
+            specifier,
+            headers: None,
+            content: content.clone(),
+            from_cache: true,
+          });
+        return Box::pin(future::ready(Ok(response)));
+      }
+    };
+    if let Some(content_type) = media_type.as_content_type() {
+      headers.insert("content-type".to_string(), content_type.to_string());
+    }
+    Box::pin(future::ready(Ok(Some(LoadResponse {
+      specifier,
+      headers: Some(headers),
+      content,
+      from_cache: true,
+    }))))
+  }
+}
+
+/// Substitutes any specifier variables, normalizes remote specifiers, and
+/// resolves with the import map, if any, then falls back to resolving
+/// bare specifiers from the node_modules directory, if any.
+#[derive(Debug, Default)]
+struct GraphResolver {
+  import_map: Option<ImportMapResolver>,
+  node_modules: Option<NodeModulesResolver>,
+  specifier_vars: HashMap<String, String>,
+  cdn_normalization: Vec<(Regex, String)>,
+}
+
+impl GraphResolver {
+  /// Resolves with the provided resolver, if any, in place of the import
+  /// map.
+  fn resolve_with(
+    &self,
+    resolver: Option<&dyn Resolver>,
+    specifier: &str,
+    referrer_range: &Range,
+    mode: ResolutionMode,
+  ) -> Result<ModuleSpecifier, ResolveError> {
+    let specifier = substitute_specifier_vars(specifier, &self.specifier_vars)
+      .map_err(ResolveError::Other)?;
+    let specifier = normalize_cdn_specifier(
+      specifier.as_ref(),
+      &referrer_range.specifier,
+      &self.cdn_normalization,
+    );
+    let specifier = specifier.as_ref();
+    let result = match resolver {
+      Some(resolver) => resolver.resolve(specifier, referrer_range, mode),
+      None => deno_graph::resolve_import(specifier, &referrer_range.specifier)
+        .map_err(ResolveError::Specifier),
+    };
+    match (result, &self.node_modules) {
+      (Err(err), Some(node_modules)) => node_modules
+        .resolve(specifier, &referrer_range.specifier)
+        .map_err(|err| ResolveError::Other(err.into()))?
+        .ok_or(err),
+      (result, _) => result,
+    }
+  }
+}
+
+impl deno_graph::source::Resolver for GraphResolver {
+  fn resolve(
+    &self,
+    specifier: &str,
+    referrer_range: &Range,
+    mode: ResolutionMode,
+  ) -> Result<ModuleSpecifier, ResolveError> {
+    let import_map = self.import_map.as_ref().map(|r| r as &dyn Resolver);
+    self.resolve_with(import_map, specifier, referrer_range, mode)
+  }
+}
+
+/// A `GraphResolver` that uses another resolver in place of its import map.
+#[derive(Debug)]
+struct ReplacedImportMapResolver<'a> {
+  graph_resolver: &'a GraphResolver,
+  resolver: &'a dyn Resolver,
+}
+
+impl<'a> deno_graph::source::Resolver for ReplacedImportMapResolver<'a> {
+  fn resolve(
+    &self,
+    specifier: &str,
+    referrer_range: &Range,
+    mode: ResolutionMode,
+  ) -> Result<ModuleSpecifier, ResolveError> {
+    self.graph_resolver.resolve_with(
+      Some(self.resolver),
+      specifier,
+      referrer_range,
+      mode,
+    )
+  }
+}
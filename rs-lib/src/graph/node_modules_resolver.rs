@@ -0,0 +1,528 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use anyhow::Context;
+use anyhow::Result;
+use deno_ast::ModuleSpecifier;
+use deno_graph::source::CacheSetting;
+use deno_graph::source::LoaderChecksum;
+use deno_graph::source::ResolveError;
+use deno_graph::ResolutionError;
+use futures::future::LocalBoxFuture;
+use futures::future::Shared;
+use futures::Future;
+use futures::FutureExt;
+use serde_json::Value;
+
+use crate::utils::url_to_file_path;
+use crate::LoadResponse;
+use crate::Loader;
+
+type SharedPackageJson =
+  Shared<LocalBoxFuture<'static, Result<Option<Rc<Value>>, Rc<anyhow::Error>>>>;
+
+/// Resolves bare specifiers to files in `node_modules` directories like
+/// node, looking in the `node_modules` directory of the referrer's
+/// directory and each of its ancestors, then in the configured directory.
+/// Packages are resolved with the `exports` field of their package.json,
+/// including subpath patterns (ex. `"./*": "./dist/*.js"`), otherwise with
+/// the `main` field.
+///
+/// Resolution is synchronous, so the package.json files are loaded ahead
+/// of time by the loader from `NodeModulesResolver::loader`, which reads
+/// those of the packages in the `node_modules` directories of each module
+/// before returning it. Packages are found and files are checked for with
+/// `Loader::list_dir`.
+#[derive(Clone)]
+pub struct NodeModulesResolver {
+  dir: Option<ModuleSpecifier>,
+  loader: Rc<dyn Loader>,
+  dir_entries: Rc<RefCell<HashMap<ModuleSpecifier, Rc<Vec<String>>>>>,
+  package_jsons: Rc<RefCell<HashMap<ModuleSpecifier, SharedPackageJson>>>,
+}
+
+impl fmt::Debug for NodeModulesResolver {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("NodeModulesResolver")
+      .field("dir", &self.dir)
+      .finish()
+  }
+}
+
+impl NodeModulesResolver {
+  pub fn new(dir: &Path, loader: Rc<dyn Loader>) -> Self {
+    Self {
+      dir: dir_path_to_specifier(dir),
+      loader,
+      dir_entries: Default::default(),
+      package_jsons: Default::default(),
+    }
+  }
+
+  /// Gets a loader that wraps this resolver's loader and loads the
+  /// package.json files used to resolve the imports of each module.
+  pub fn loader(&self) -> Rc<dyn Loader> {
+    Rc::new(NodeModulesLoader {
+      resolver: self.clone(),
+    })
+  }
+
+  /// Resolves the bare specifier or returns `None` if it's not a bare
+  /// specifier or the package isn't found. Errors when the package's
+  /// package.json couldn't be loaded or parsed.
+  pub fn resolve(
+    &self,
+    specifier: &str,
+    referrer: &ModuleSpecifier,
+  ) -> Result<Option<ModuleSpecifier>, PackageJsonError> {
+    let Some((package_name, sub_path)) = parse_bare_specifier(specifier) else {
+      return Ok(None);
+    };
+    for dir in self.node_modules_dirs(referrer) {
+      let Ok(package_dir) = dir.join(&format!("{}/", package_name)) else {
+        continue;
+      };
+      let package_json = self
+        .package_jsons
+        .borrow()
+        .get(&package_dir)
+        .and_then(|package_json| package_json.peek().cloned());
+      let Some(package_json) = package_json else {
+        continue;
+      };
+      let package_json =
+        package_json.map_err(|err| PackageJsonError(format!("{:#}", err)))?;
+      if let Some(specifier) =
+        self.resolve_in_package(&package_dir, package_json.as_deref(), sub_path)
+      {
+        return Ok(Some(specifier));
+      }
+    }
+    Ok(None)
+  }
+
+  /// Loads the package.json files of the packages the referrer may import.
+  fn load_packages(
+    &self,
+    referrer: &ModuleSpecifier,
+  ) -> impl Future<Output = ()> {
+    let package_jsons = self
+      .node_modules_dirs(referrer)
+      .iter()
+      .flat_map(|dir| self.package_dirs(dir))
+      .map(|package_dir| self.load_package_json(package_dir))
+      .collect::<Vec<_>>();
+    // errors are surfaced when resolving an import of the package
+    futures::future::join_all(package_jsons).map(|_| ())
+  }
+
+  fn load_package_json(
+    &self,
+    package_dir: ModuleSpecifier,
+  ) -> SharedPackageJson {
+    if let Some(package_json) = self.package_jsons.borrow().get(&package_dir) {
+      return package_json.clone();
+    }
+    let specifier = package_dir.join("package.json").unwrap();
+    let load = self.loader.load(specifier.clone(), CacheSetting::Use, None);
+    let package_json = async move {
+      let result = async {
+        let Some(response) = load
+          .await
+          .with_context(|| format!("Error loading {}", specifier))?
+        else {
+          return Ok(None);
+        };
+        let value = serde_json::from_slice(&response.content)
+          .with_context(|| format!("Error parsing {}", specifier))?;
+        Ok(Some(Rc::new(value)))
+      };
+      result.await.map_err(Rc::new)
+    }
+    .boxed_local()
+    .shared();
+    self
+      .package_jsons
+      .borrow_mut()
+      .insert(package_dir, package_json.clone());
+    package_json
+  }
+
+  /// Gets the `node_modules` directories to look for packages in, in order.
+  fn node_modules_dirs(
+    &self,
+    referrer: &ModuleSpecifier,
+  ) -> Vec<ModuleSpecifier> {
+    let mut dirs = Vec::new();
+    if referrer.scheme() == "file" {
+      let mut dir = referrer.join(".").ok();
+      while let Some(current) = dir {
+        // node doesn't look in `node_modules/node_modules`
+        if !current.path().ends_with("/node_modules/") {
+          if let Ok(node_modules) = current.join("node_modules/") {
+            dirs.push(node_modules);
+          }
+        }
+        dir = if current.path() == "/" {
+          None
+        } else {
+          current.join("..").ok()
+        };
+      }
+    }
+    if let Some(dir) = &self.dir {
+      if !dirs.contains(dir) {
+        dirs.push(dir.clone());
+      }
+    }
+    dirs
+  }
+
+  /// Gets the directories of the packages in a `node_modules` directory,
+  /// including scoped packages (ex. `@scope/pkg`).
+  fn package_dirs(
+    &self,
+    node_modules_dir: &ModuleSpecifier,
+  ) -> Vec<ModuleSpecifier> {
+    let mut package_dirs = Vec::new();
+    for name in self.dir_entries(node_modules_dir).iter() {
+      // ex. `.bin` or `.package-lock.json`
+      if name.starts_with('.') {
+        continue;
+      }
+      let Ok(dir) = node_modules_dir.join(&format!("{}/", name)) else {
+        continue;
+      };
+      if name.starts_with('@') {
+        package_dirs.extend(
+          self
+            .dir_entries(&dir)
+            .iter()
+            .filter_map(|name| dir.join(&format!("{}/", name)).ok()),
+        );
+      } else {
+        package_dirs.push(dir);
+      }
+    }
+    package_dirs
+  }
+
+  fn resolve_in_package(
+    &self,
+    package_dir: &ModuleSpecifier,
+    package_json: Option<&Value>,
+    sub_path: &str,
+  ) -> Option<ModuleSpecifier> {
+    match package_json.and_then(|p| p.get("exports")) {
+      Some(exports) => {
+        let subpath = if sub_path.is_empty() {
+          ".".to_string()
+        } else {
+          format!("./{}", sub_path)
+        };
+        let target = resolve_exports(exports, &subpath)?;
+        let specifier = package_dir.join(&target).ok()?;
+        self.is_file(&specifier).then_some(specifier)
+      }
+      None if sub_path.is_empty() => {
+        match package_json
+          .and_then(|p| p.get("main"))
+          .and_then(|m| m.as_str())
+        {
+          Some(main) => self.resolve_file(package_dir, main),
+          None => self.resolve_file(package_dir, "index.js"),
+        }
+      }
+      None => self.resolve_file(package_dir, sub_path),
+    }
+  }
+
+  /// Resolves a file path the same way as node for a `main` entry or
+  /// a sub path without an `exports` field.
+  fn resolve_file(
+    &self,
+    package_dir: &ModuleSpecifier,
+    path: &str,
+  ) -> Option<ModuleSpecifier> {
+    let path = path.trim_end_matches('/');
+    [
+      path.to_string(),
+      format!("{}.js", path),
+      format!("{}/index.js", path),
+    ]
+    .into_iter()
+    .filter_map(|path| package_dir.join(&path).ok())
+    .find(|specifier| self.is_file(specifier))
+  }
+
+  /// Checks if the file is in its directory's entries and isn't itself a
+  /// directory with entries.
+  fn is_file(&self, specifier: &ModuleSpecifier) -> bool {
+    let Ok(file_path) = url_to_file_path(specifier) else {
+      return false;
+    };
+    let (Some(name), Ok(dir)) = (file_path.file_name(), specifier.join("."))
+    else {
+      return false;
+    };
+    let mut as_dir = specifier.clone();
+    as_dir.set_path(&format!("{}/", specifier.path()));
+    self
+      .dir_entries(&dir)
+      .iter()
+      .any(|entry| name.to_string_lossy() == entry.as_str())
+      && self.dir_entries(&as_dir).is_empty()
+  }
+
+  fn dir_entries(&self, dir: &ModuleSpecifier) -> Rc<Vec<String>> {
+    if let Some(entries) = self.dir_entries.borrow().get(dir) {
+      return entries.clone();
+    }
+    let entries = Rc::new(self.loader.list_dir(dir).unwrap_or_default());
+    self
+      .dir_entries
+      .borrow_mut()
+      .insert(dir.clone(), entries.clone());
+    entries
+  }
+}
+
+/// An error loading or parsing the package.json of an imported package.
+#[derive(Debug)]
+pub struct PackageJsonError(String);
+
+impl fmt::Display for PackageJsonError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+impl std::error::Error for PackageJsonError {}
+
+impl PackageJsonError {
+  /// Checks if the resolution error is from a package.json that couldn't
+  /// be loaded or parsed.
+  pub fn is_resolution_error(error: &ResolutionError) -> bool {
+    match error {
+      ResolutionError::ResolverError { error, .. } => {
+        matches!(&**error, ResolveError::Other(err) if err.is::<Self>())
+      }
+      _ => false,
+    }
+  }
+}
+
+/// Loader that loads the package.json files used to resolve a module's
+/// bare specifiers before returning the module.
+struct NodeModulesLoader {
+  resolver: NodeModulesResolver,
+}
+
+impl Loader for NodeModulesLoader {
+  fn list_dir(&self, dir: &ModuleSpecifier) -> Option<Vec<String>> {
+    self.resolver.loader.list_dir(dir)
+  }
+
+  fn load(
+    &self,
+    specifier: ModuleSpecifier,
+    cache_setting: CacheSetting,
+    maybe_checksum: Option<LoaderChecksum>,
+  ) -> Pin<Box<dyn Future<Output = Result<Option<LoadResponse>>> + 'static>> {
+    let load =
+      self
+        .resolver
+        .loader
+        .load(specifier, cache_setting, maybe_checksum);
+    let resolver = self.resolver.clone();
+    Box::pin(async move {
+      let response = load.await?;
+      if let Some(response) = &response {
+        resolver.load_packages(&response.specifier).await;
+      }
+      Ok(response)
+    })
+  }
+}
+
+/// Gets the `file:` specifier of a directory, which ends with a slash.
+fn dir_path_to_specifier(path: &Path) -> Option<ModuleSpecifier> {
+  let mut text = path.to_string_lossy().replace('\\', "/");
+  if !text.starts_with('/') {
+    // windows paths (ex. `C:/dir`)
+    text.insert(0, '/');
+  }
+  if !text.ends_with('/') {
+    text.push('/');
+  }
+  ModuleSpecifier::parse(&format!("file://{}", text)).ok()
+}
+
+/// Gets the target of the subpath (ex. `.` or `./sub`) in a package's
+/// `exports` field, with the `*` of a matching subpath pattern replaced.
+fn resolve_exports(exports: &Value, subpath: &str) -> Option<String> {
+  let map = match exports {
+    Value::Object(map) if map.keys().any(|k| k.starts_with('.')) => map,
+    // a target or conditions for the main export
+    _ => {
+      return match subpath {
+        "." => resolve_target(exports, None),
+        _ => None,
+      }
+    }
+  };
+  if !subpath.contains('*') {
+    if let Some(target) = map.get(subpath) {
+      return resolve_target(target, None);
+    }
+  }
+  // the pattern with the longest prefix, then the longest key, is used
+  let (key, matched) = map
+    .keys()
+    .filter_map(|key| {
+      let (base, trailer) = key.split_once('*')?;
+      if trailer.contains('*') {
+        return None;
+      }
+      let matched = subpath.strip_prefix(base)?.strip_suffix(trailer)?;
+      if matched.is_empty() {
+        return None;
+      }
+      Some((key, matched))
+    })
+    .max_by_key(|(key, _)| (key.find('*').unwrap(), key.len()))?;
+  resolve_target(&map[key], Some(matched))
+}
+
+/// Gets the target with the conditions resolved, which must be relative to
+/// the package directory.
+fn resolve_target(
+  value: &Value,
+  pattern_match: Option<&str>,
+) -> Option<String> {
+  let target = resolve_conditions(value)?;
+  if !target.starts_with("./") {
+    return None;
+  }
+  Some(match pattern_match {
+    Some(pattern_match) => target.replace('*', pattern_match),
+    None => target.to_string(),
+  })
+}
+
+/// Splits a bare specifier into its package name and sub path.
+fn parse_bare_specifier(specifier: &str) -> Option<(&str, &str)> {
+  if specifier.starts_with('.')
+    || specifier.starts_with('/')
+    || specifier.is_empty()
+    || ModuleSpecifier::parse(specifier).is_ok()
+  {
+    return None;
+  }
+  let name_end = if specifier.starts_with('@') {
+    let scope_end = specifier.find('/')?;
+    specifier[scope_end + 1..]
+      .find('/')
+      .map(|index| scope_end + 1 + index)
+  } else {
+    specifier.find('/')
+  };
+  Some(match name_end {
+    Some(index) => (&specifier[..index], &specifier[index + 1..]),
+    None => (specifier, ""),
+  })
+}
+
+/// Gets the target of an export, preferring the conditions used by ESM.
+fn resolve_conditions(value: &Value) -> Option<&str> {
+  match value {
+    Value::String(target) => Some(target),
+    Value::Object(map) => ["import", "node", "default", "require"]
+      .iter()
+      .find_map(|condition| map.get(*condition).and_then(resolve_conditions)),
+    Value::Array(values) => values.iter().find_map(resolve_conditions),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn parses_bare_specifiers() {
+    assert_eq!(parse_bare_specifier("pkg"), Some(("pkg", "")));
+    assert_eq!(
+      parse_bare_specifier("pkg/sub/a.js"),
+      Some(("pkg", "sub/a.js"))
+    );
+    assert_eq!(parse_bare_specifier("@scope/pkg"), Some(("@scope/pkg", "")));
+    assert_eq!(
+      parse_bare_specifier("@scope/pkg/sub"),
+      Some(("@scope/pkg", "sub"))
+    );
+    assert_eq!(parse_bare_specifier("@scope"), None);
+    assert_eq!(parse_bare_specifier("./a.js"), None);
+    assert_eq!(parse_bare_specifier("/a.js"), None);
+    assert_eq!(parse_bare_specifier("https://localhost/a.js"), None);
+    assert_eq!(parse_bare_specifier("npm:pkg"), None);
+  }
+
+  #[test]
+  fn resolves_export_conditions() {
+    let value = serde_json::json!({
+      "types": "./index.d.ts",
+      "require": "./index.cjs",
+      "import": "./index.mjs",
+    });
+    assert_eq!(resolve_conditions(&value), Some("./index.mjs"));
+    let value = serde_json::json!([{ "node": "./node.js" }, "./index.js"]);
+    assert_eq!(resolve_conditions(&value), Some("./node.js"));
+  }
+
+  #[test]
+  fn resolves_exports() {
+    let exports = serde_json::json!({
+      ".": { "import": "./index.mjs" },
+      "./package.json": "./package.json",
+      "./*": "./dist/*.js",
+      "./utils/*": { "import": "./dist/utils/*.mjs" },
+      "./internal/*": null,
+      "./*.css": "./styles/*.css",
+      "./outside": "../outside.js",
+    });
+    let resolve = |subpath: &str| resolve_exports(&exports, subpath);
+    assert_eq!(resolve("."), Some("./index.mjs".to_string()));
+    assert_eq!(
+      resolve("./package.json"),
+      Some("./package.json".to_string())
+    );
+    assert_eq!(resolve("./a"), Some("./dist/a.js".to_string()));
+    assert_eq!(resolve("./a/b"), Some("./dist/a/b.js".to_string()));
+    assert_eq!(resolve("./utils/a"), Some("./dist/utils/a.mjs".to_string()));
+    assert_eq!(resolve("./internal/a"), None);
+    assert_eq!(
+      resolve("./theme.css"),
+      Some("./styles/theme.css".to_string())
+    );
+    assert_eq!(resolve("./outside"), None);
+
+    let exports = serde_json::json!({ "import": "./index.mjs" });
+    assert_eq!(
+      resolve_exports(&exports, "."),
+      Some("./index.mjs".to_string())
+    );
+    assert_eq!(resolve_exports(&exports, "./sub"), None);
+    let exports = serde_json::json!("./index.js");
+    assert_eq!(
+      resolve_exports(&exports, "."),
+      Some("./index.js".to_string())
+    );
+  }
+}
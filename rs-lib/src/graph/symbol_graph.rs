@@ -0,0 +1,262 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use deno_ast::ModuleSpecifier;
+use deno_ast::ParsedSource;
+use deno_graph::Range;
+
+use super::to_range;
+use super::ModuleGraph;
+use crate::analyze::get_module_symbols;
+use crate::analyze::ModuleSymbolInfo;
+use crate::analyze::SymbolExport;
+use crate::analyze::SymbolImport;
+
+/// The exports and import bindings of each module in a graph, with each
+/// one linked to the declaration it refers to.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SymbolGraph {
+  pub modules: BTreeMap<ModuleSpecifier, ModuleSymbols>,
+}
+
+impl SymbolGraph {
+  /// Gets the import bindings in all modules that resolve to the
+  /// declaration at the provided range.
+  pub fn references(&self, declaration: &Range) -> Vec<&ImportBinding> {
+    self
+      .modules
+      .values()
+      .flat_map(|module| module.imports.iter())
+      .filter(|import| {
+        import.declaration.as_ref().map(|d| &d.range) == Some(declaration)
+      })
+      .collect()
+  }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ModuleSymbols {
+  /// Exports of the module, not including the names provided by
+  /// `export * from "..."`.
+  pub exports: Vec<ExportSymbol>,
+  pub imports: Vec<ImportBinding>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportSymbol {
+  pub name: String,
+  pub range: Range,
+  /// The declaration the export resolves to, which is `None` for
+  /// namespace re-exports and exports that couldn't be resolved.
+  pub declaration: Option<SymbolDeclaration>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportBinding {
+  /// Name of the binding in the importing module.
+  pub local: String,
+  /// Name of the export, which is `default` for default imports and
+  /// `*` for namespace imports.
+  pub imported: String,
+  pub range: Range,
+  /// The module the binding is imported from.
+  pub specifier: Option<ModuleSpecifier>,
+  /// The declaration the binding resolves to, following any re-exports.
+  /// This is `None` for namespace imports and imports that couldn't be
+  /// resolved (ex. from npm packages).
+  pub declaration: Option<SymbolDeclaration>,
+}
+
+/// A top level declaration (or anonymous default export) of a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolDeclaration {
+  /// Name of the declaration in its module, which is `default` for
+  /// anonymous default exports.
+  pub name: String,
+  pub range: Range,
+}
+
+impl ModuleGraph {
+  /// Gets the exports and import bindings of every module in the graph,
+  /// linking each to the declaration it resolves to across modules.
+  pub fn symbol_graph(&self) -> SymbolGraph {
+    let mut context = Context {
+      graph: self,
+      modules: HashMap::new(),
+    };
+    let mut symbol_graph = SymbolGraph::default();
+    for module in self.graph.modules().filter_map(|m| m.js()) {
+      let Some(module) = context.module(&module.specifier) else {
+        continue;
+      };
+      let mut symbols = ModuleSymbols::default();
+      for export in &module.info.exports {
+        let (name, range) = match export {
+          SymbolExport::Local { name, range, .. }
+          | SymbolExport::ReExport { name, range, .. } => (name, range),
+          SymbolExport::Star { .. } => continue,
+        };
+        symbols.exports.push(ExportSymbol {
+          name: name.clone(),
+          range: to_range(&module.parsed_source, *range),
+          declaration: context.resolve_export_entry(
+            &module,
+            export,
+            &mut HashSet::new(),
+          ),
+        });
+      }
+      for import in &module.info.imports {
+        let specifier =
+          self.resolve_dependency(&import.src, module.specifier());
+        symbols.imports.push(ImportBinding {
+          local: import.local.0.to_string(),
+          imported: import.imported.clone(),
+          range: to_range(&module.parsed_source, import.range),
+          specifier,
+          declaration: context.resolve_import(
+            &module,
+            import,
+            &mut HashSet::new(),
+          ),
+        });
+      }
+      symbol_graph
+        .modules
+        .insert(module.specifier().clone(), symbols);
+    }
+    symbol_graph
+  }
+}
+
+struct AnalyzedModule {
+  parsed_source: ParsedSource,
+  info: ModuleSymbolInfo,
+}
+
+impl AnalyzedModule {
+  fn specifier(&self) -> &ModuleSpecifier {
+    self.parsed_source.specifier()
+  }
+}
+
+struct Context<'a> {
+  graph: &'a ModuleGraph,
+  modules: HashMap<ModuleSpecifier, Rc<AnalyzedModule>>,
+}
+
+impl<'a> Context<'a> {
+  fn module(
+    &mut self,
+    specifier: &ModuleSpecifier,
+  ) -> Option<Rc<AnalyzedModule>> {
+    let parsed_source = self.graph.maybe_parsed_source(specifier)?;
+    let module = self
+      .modules
+      .entry(parsed_source.specifier().clone())
+      .or_insert_with(|| {
+        Rc::new(AnalyzedModule {
+          info: parsed_source.with_view(get_module_symbols),
+          parsed_source,
+        })
+      });
+    Some(module.clone())
+  }
+
+  fn resolve_export(
+    &mut self,
+    specifier: &ModuleSpecifier,
+    name: &str,
+    visited: &mut HashSet<(ModuleSpecifier, String)>,
+  ) -> Option<SymbolDeclaration> {
+    let module = self.module(specifier)?;
+    if !visited.insert((module.specifier().clone(), name.to_string())) {
+      return None;
+    }
+    let export = module.info.exports.iter().find(|export| match export {
+      SymbolExport::Local { name: n, .. }
+      | SymbolExport::ReExport { name: n, .. } => n == name,
+      SymbolExport::Star { .. } => false,
+    });
+    if let Some(export) = export {
+      return self.resolve_export_entry(&module, export, visited);
+    }
+    // the default export is not included in star exports
+    if name == "default" {
+      return None;
+    }
+    for export in &module.info.exports {
+      if let SymbolExport::Star { src } = export {
+        let declaration = self
+          .graph
+          .resolve_dependency(src, module.specifier())
+          .and_then(|specifier| self.resolve_export(&specifier, name, visited));
+        if declaration.is_some() {
+          return declaration;
+        }
+      }
+    }
+    None
+  }
+
+  fn resolve_export_entry(
+    &mut self,
+    module: &AnalyzedModule,
+    export: &SymbolExport,
+    visited: &mut HashSet<(ModuleSpecifier, String)>,
+  ) -> Option<SymbolDeclaration> {
+    match export {
+      SymbolExport::Local {
+        local: Some(local), ..
+      } => {
+        if let Some(import) =
+          module.info.imports.iter().find(|i| &i.local == local)
+        {
+          self.resolve_import(module, import, visited)
+        } else {
+          let range = module.info.declarations.get(local)?;
+          Some(SymbolDeclaration {
+            name: local.0.to_string(),
+            range: to_range(&module.parsed_source, *range),
+          })
+        }
+      }
+      SymbolExport::Local {
+        name,
+        local: None,
+        range,
+      } => Some(SymbolDeclaration {
+        name: name.clone(),
+        range: to_range(&module.parsed_source, *range),
+      }),
+      SymbolExport::ReExport { imported, src, .. } => {
+        if imported == "*" {
+          return None;
+        }
+        let specifier =
+          self.graph.resolve_dependency(src, module.specifier())?;
+        self.resolve_export(&specifier, imported, visited)
+      }
+      SymbolExport::Star { .. } => None,
+    }
+  }
+
+  fn resolve_import(
+    &mut self,
+    module: &AnalyzedModule,
+    import: &SymbolImport,
+    visited: &mut HashSet<(ModuleSpecifier, String)>,
+  ) -> Option<SymbolDeclaration> {
+    if import.imported == "*" {
+      return None;
+    }
+    let specifier = self
+      .graph
+      .resolve_dependency(&import.src, module.specifier())?;
+    self.resolve_export(&specifier, &import.imported, visited)
+  }
+}
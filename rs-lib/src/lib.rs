@@ -20,14 +20,12 @@ use anyhow::bail;
 use deno_ast::apply_text_changes;
 use deno_ast::TextChange;
 use deno_graph::Module;
-use graph::ModuleGraphOptions;
 use mappings::Mappings;
 use mappings::SYNTHETIC_SPECIFIERS;
 use mappings::SYNTHETIC_TEST_SPECIFIERS;
 use polyfills::build_polyfill_file;
 use polyfills::polyfills_for_target;
 use polyfills::Polyfill;
-use specifiers::Specifiers;
 use utils::get_relative_specifier;
 use utils::prepend_statement_to_text;
 use visitors::fill_polyfills;
@@ -38,11 +36,55 @@ use visitors::FillPolyfillsParams;
 use visitors::GetGlobalTextChangesParams;
 use visitors::GetImportExportsTextChangesParams;
 
+pub use analyze::SlowTypeKind;
+pub use analyze::TsFeatureSet;
+pub use analyze::WasmExport;
+pub use analyze::WasmExternalKind;
+pub use analyze::WasmImport;
+pub use analyze::WasmModuleInfo;
+pub use deno_ast::MediaType;
 pub use deno_ast::ModuleSpecifier;
 pub use deno_graph::source::CacheSetting;
+pub use deno_graph::source::JsrUrlProvider;
 pub use deno_graph::source::LoaderChecksum;
+pub use deno_graph::source::Reporter;
+pub use deno_graph::source::Resolver;
+pub use deno_graph::Range;
+pub use futures::future::AbortHandle;
+pub use futures::future::AbortRegistration;
+pub use graph::default_index_extensions;
+pub use graph::default_module_extensions;
+pub use graph::Collision;
+pub use graph::Cycle;
+pub use graph::CycleKind;
+pub use graph::DenoApiUsage;
+pub use graph::ExportSymbol;
+pub use graph::ExternalDependency;
+pub use graph::GraphBuildError;
+pub use graph::ImportBinding;
+pub use graph::ImportMetaUsage;
+pub use graph::ModuleGraph;
+pub use graph::ModuleGraphOptions;
+pub use graph::ModuleGraphView;
+pub use graph::ModuleLoadError;
+pub use graph::ModuleLoadErrorKind;
+pub use graph::ModuleSymbols;
+pub use graph::ParseWarning;
+pub use graph::SlowTypeDiagnostic;
+pub use graph::SymbolDeclaration;
+pub use graph::SymbolGraph;
+pub use loader::CacheStats;
+pub use loader::EncodingError;
+pub use loader::HostPattern;
 pub use loader::LoadResponse;
 pub use loader::Loader;
+pub use loader::PackageVersionSource;
+#[cfg(feature = "vfs-loader")]
+pub use loader::VfsLoader;
+pub use parser::SyntaxOptions;
+pub use specifiers::Specifiers;
+pub use utils::common_ancestor;
+pub use utils::get_deno_json_entry_points;
 pub use utils::url_to_file_path;
 
 use crate::declaration_file_resolution::TypesDependency;
@@ -70,7 +112,7 @@ pub struct OutputFile {
 #[cfg_attr(feature = "serialization", derive(serde::Serialize))]
 #[cfg_attr(feature = "serialization", derive(serde::Deserialize))]
 #[cfg_attr(feature = "serialization", serde(rename_all = "camelCase"))]
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub struct Dependency {
   pub name: String,
   pub version: String,
@@ -116,7 +158,9 @@ pub struct PackageMappedSpecifier {
   /// Version of the specifier. Leave this blank to not have a
   /// dependency (ex. Node modules like "fs")
   pub version: Option<String>,
-  /// Sub path of the npm package to use in the module specifier.
+  /// Sub path of the npm package to use in the module specifier. This
+  /// doesn't need to match the path of the mapped module, so
+  /// `https://deno.land/x/foo/mod.ts` may be mapped to `@org/foo/main`.
   pub sub_path: Option<String>,
   /// If this is suggested to be a peer dependency.
   #[serde(default)]
@@ -124,7 +168,9 @@ pub struct PackageMappedSpecifier {
 }
 
 impl PackageMappedSpecifier {
-  pub(crate) fn module_specifier_text(&self) -> String {
+  /// The text of the module specifier that imports of the mapped
+  /// module are rewritten to (ex. `@org/foo/main`).
+  pub fn module_specifier_text(&self) -> String {
     if let Some(path) = &self.sub_path {
       format!("{}/{}", self.name, path)
     } else {
@@ -235,6 +281,17 @@ pub struct TransformOptions {
   pub target: ScriptTarget,
   /// Optional import map.
   pub import_map: Option<ModuleSpecifier>,
+  /// Registry that `jsr:` specifiers are resolved from. Defaults to
+  /// `https://jsr.io/`.
+  pub jsr_url: Option<ModuleSpecifier>,
+}
+
+struct JsrUrl(ModuleSpecifier);
+
+impl JsrUrlProvider for JsrUrl {
+  fn url(&self) -> &ModuleSpecifier {
+    &self.0
+  }
 }
 
 struct EnvironmentContext<'a> {
@@ -252,6 +309,7 @@ pub async fn transform(options: TransformOptions) -> Result<TransformOutput> {
     anyhow::bail!("at least one entry point must be specified");
   }
 
+  let jsr_url_provider = options.jsr_url.map(JsrUrl);
   let (module_graph, specifiers) =
     crate::graph::ModuleGraph::build_with_specifiers(ModuleGraphOptions {
       entry_points: options
@@ -274,6 +332,10 @@ pub async fn transform(options: TransformOptions) -> Result<TransformOutput> {
       specifier_mappings: &options.specifier_mappings,
       loader: options.loader,
       import_map: options.import_map,
+      jsr_url_provider: jsr_url_provider
+        .as_ref()
+        .map(|p| p as &dyn JsrUrlProvider),
+      ..Default::default()
     })
     .await?;
 
@@ -288,6 +350,7 @@ pub async fn transform(options: TransformOptions) -> Result<TransformOutput> {
       .collect();
 
   let mut warnings = get_declaration_warnings(&specifiers);
+  warnings.extend(module_graph.warnings().iter().cloned());
   let mut main_env_context = EnvironmentContext {
     environment: TransformOutputEnvironment {
       entry_points: options
@@ -346,6 +409,12 @@ pub async fn transform(options: TransformOptions) -> Result<TransformOutput> {
 
     let file_text = match module {
       Module::Js(_) => {
+        for range in module_graph.unanalyzable_dynamic_imports(specifier) {
+          warnings.push(format!(
+            "Dynamic import with a non-literal specifier will not be rewritten.\n    at {}",
+            range,
+          ));
+        }
         let parsed_source = module_graph.get_parsed_source(specifier);
         let text_changes = parsed_source
           .with_view(|program| -> Result<Vec<TextChange>> {
@@ -2,32 +2,69 @@
 
 use std::io::ErrorKind;
 use std::pin::Pin;
+use std::rc::Rc;
 
+use anyhow::bail;
+use anyhow::Context;
 use anyhow::Result;
 use deno_ast::ModuleSpecifier;
 use deno_graph::source::CacheSetting;
 use deno_graph::source::LoaderChecksum;
 use futures::Future;
 
+use crate::loader::RedirectCallback;
 use crate::utils::url_to_file_path;
 use crate::LoadResponse;
 use crate::Loader;
 
-pub struct DefaultLoader {}
+const MAX_REDIRECTS: usize = 10;
+
+pub struct DefaultLoader {
+  client: reqwest::Client,
+  on_redirect: Option<Rc<RedirectCallback>>,
+}
 
 impl DefaultLoader {
   pub fn new() -> Self {
-    Self {}
+    Self {
+      // redirects are followed manually in order to report each one
+      client: reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap(),
+      on_redirect: None,
+    }
+  }
+
+  /// Sets a callback that's invoked with the original and redirected
+  /// specifier for each redirect that occurs while loading. Returning an
+  /// error from it aborts the load.
+  pub fn with_on_redirect(mut self, on_redirect: Rc<RedirectCallback>) -> Self {
+    self.on_redirect = Some(on_redirect);
+    self
   }
 }
 
 impl Loader for DefaultLoader {
+  fn list_dir(&self, dir: &ModuleSpecifier) -> Option<Vec<String>> {
+    let entries = std::fs::read_dir(url_to_file_path(dir).ok()?).ok()?;
+    Some(
+      entries
+        .filter_map(|entry| {
+          Some(entry.ok()?.file_name().to_string_lossy().to_string())
+        })
+        .collect(),
+    )
+  }
+
   fn load(
     &self,
     specifier: ModuleSpecifier,
     _cache_setting: CacheSetting,
     maybe_checksum: Option<LoaderChecksum>,
   ) -> Pin<Box<dyn Future<Output = Result<Option<LoadResponse>>> + 'static>> {
+    let client = self.client.clone();
+    let on_redirect = self.on_redirect.clone();
     Box::pin(async move {
       if specifier.scheme() == "file" {
         let file_path = url_to_file_path(&specifier)?;
@@ -40,6 +77,7 @@ impl Loader for DefaultLoader {
               specifier,
               content: bytes,
               headers: None,
+              from_cache: false,
             }))
           }
           Err(err) => {
@@ -52,7 +90,25 @@ impl Loader for DefaultLoader {
         };
       }
 
-      let response = reqwest::get(specifier.clone()).await?;
+      let response =
+        follow_redirects(specifier, on_redirect.as_deref(), |url| {
+          let request = client.get(url.clone()).send();
+          async move {
+            let response = request.await?;
+            let location = response
+              .headers()
+              .get(reqwest::header::LOCATION)
+              .and_then(|value| value.to_str().ok());
+            let redirect_url = match location {
+              Some(location) if response.status().is_redirection() => {
+                Some(url.join(location)?)
+              }
+              _ => None,
+            };
+            Ok((response, redirect_url))
+          }
+        })
+        .await?;
       let headers = response
         .headers()
         .into_iter()
@@ -72,7 +128,154 @@ impl Loader for DefaultLoader {
         specifier: final_url,
         content: bytes.into(),
         headers: Some(headers),
+        from_cache: false,
       }))
     })
   }
 }
+
+/// Requests the url with `fetch`, which provides the response along with
+/// the url it redirects to, until a response that isn't a redirect.
+async fn follow_redirects<TResponse, TFuture>(
+  specifier: ModuleSpecifier,
+  on_redirect: Option<&RedirectCallback>,
+  mut fetch: impl FnMut(ModuleSpecifier) -> TFuture,
+) -> Result<TResponse>
+where
+  TFuture: Future<Output = Result<(TResponse, Option<ModuleSpecifier>)>>,
+{
+  let mut url = specifier;
+  let mut redirect_count = 0;
+  loop {
+    let (response, redirect_url) = fetch(url.clone()).await?;
+    let Some(redirect_url) = redirect_url else {
+      return Ok(response);
+    };
+    redirect_count += 1;
+    if redirect_count > MAX_REDIRECTS {
+      bail!("Too many redirects loading {}", url);
+    }
+    if let Some(on_redirect) = on_redirect {
+      on_redirect(&url, &redirect_url).with_context(|| {
+        format!("Rejected redirect from {} to {}", url, redirect_url)
+      })?;
+    }
+    url = redirect_url;
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::cell::RefCell;
+
+  use super::*;
+
+  fn specifier(text: &str) -> ModuleSpecifier {
+    ModuleSpecifier::parse(text).unwrap()
+  }
+
+  /// Fetches from a list of redirects, responding with the final url.
+  async fn fetch_with_redirects(
+    redirects: &[(&str, &str)],
+    on_redirect: Option<&RedirectCallback>,
+  ) -> Result<ModuleSpecifier> {
+    let redirects = redirects
+      .iter()
+      .map(|(from, to)| (specifier(from), specifier(to)))
+      .collect::<Vec<_>>();
+    follow_redirects(specifier("https://localhost/0"), on_redirect, |url| {
+      let redirect_url = redirects
+        .iter()
+        .find(|(from, _)| *from == url)
+        .map(|(_, to)| to.clone());
+      futures::future::ready(Ok((url, redirect_url)))
+    })
+    .await
+  }
+
+  #[tokio::test]
+  async fn follows_redirects() {
+    let redirects = Rc::new(RefCell::new(Vec::new()));
+    let on_redirect = {
+      let redirects = redirects.clone();
+      move |from: &ModuleSpecifier, to: &ModuleSpecifier| {
+        redirects
+          .borrow_mut()
+          .push((from.to_string(), to.to_string()));
+        Ok(())
+      }
+    };
+    let url = fetch_with_redirects(
+      &[
+        ("https://localhost/0", "https://localhost/1"),
+        ("https://localhost/1", "https://localhost/2"),
+      ],
+      Some(&on_redirect),
+    )
+    .await
+    .unwrap();
+    assert_eq!(url.as_str(), "https://localhost/2");
+    assert_eq!(
+      redirects.take(),
+      vec![
+        (
+          "https://localhost/0".to_string(),
+          "https://localhost/1".to_string()
+        ),
+        (
+          "https://localhost/1".to_string(),
+          "https://localhost/2".to_string()
+        ),
+      ]
+    );
+  }
+
+  #[tokio::test]
+  async fn rejects_redirects() {
+    let on_redirect = |_: &ModuleSpecifier, to: &ModuleSpecifier| {
+      if to.host_str() == Some("localhost") {
+        Ok(())
+      } else {
+        bail!("Unexpected host.")
+      }
+    };
+    let err = fetch_with_redirects(
+      &[
+        ("https://localhost/0", "https://localhost/1"),
+        ("https://localhost/1", "https://example.com/2"),
+      ],
+      Some(&on_redirect),
+    )
+    .await
+    .err()
+    .unwrap();
+    assert_eq!(
+      format!("{:#}", err),
+      "Rejected redirect from https://localhost/1 to https://example.com/2: Unexpected host."
+    );
+  }
+
+  #[tokio::test]
+  async fn errors_after_max_redirects() {
+    let urls = (0..=MAX_REDIRECTS + 1)
+      .map(|i| format!("https://localhost/{}", i))
+      .collect::<Vec<_>>();
+    let redirects = urls
+      .windows(2)
+      .map(|urls| (urls[0].as_str(), urls[1].as_str()))
+      .collect::<Vec<_>>();
+    let err = fetch_with_redirects(&redirects, None).await.err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      format!(
+        "Too many redirects loading https://localhost/{}",
+        MAX_REDIRECTS
+      )
+    );
+    // the maximum number of redirects is allowed
+    let url = fetch_with_redirects(&redirects[..MAX_REDIRECTS], None)
+      .await
+      .unwrap();
+    assert_eq!(url.as_str(), urls[MAX_REDIRECTS]);
+  }
+}
@@ -0,0 +1,128 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::ModuleSpecifier;
+
+/// Pattern for matching remote specifiers by host and optionally a path.
+///
+/// Ex. `deno.land`, `deno.land/std`, `*.example.com`, or `localhost:8000`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HostPattern {
+  host: String,
+  is_wildcard: bool,
+  path_prefix: String,
+}
+
+impl HostPattern {
+  pub fn new(text: &str) -> Self {
+    let text = text.to_lowercase();
+    let (host, path) = match text.split_once('/') {
+      Some((host, path)) => (host, path.trim_end_matches('/')),
+      None => (text.as_str(), ""),
+    };
+    let (host, is_wildcard) = match host.strip_prefix("*.") {
+      Some(host) => (host, true),
+      None => (host, false),
+    };
+    Self {
+      host: host.to_string(),
+      is_wildcard,
+      path_prefix: if path.is_empty() {
+        String::new()
+      } else {
+        format!("/{}", path)
+      },
+    }
+  }
+
+  pub fn matches(&self, specifier: &ModuleSpecifier) -> bool {
+    let Some(host) = specifier.host_str() else {
+      return false;
+    };
+    let host = if self.host.contains(':') {
+      match specifier.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+      }
+    } else {
+      host.to_string()
+    };
+    let host_matches = if self.is_wildcard {
+      host
+        .strip_suffix(&self.host)
+        .map(|prefix| prefix.ends_with('.'))
+        .unwrap_or(false)
+    } else {
+      host == self.host
+    };
+    host_matches && self.path_matches(specifier.path())
+  }
+
+  fn path_matches(&self, path: &str) -> bool {
+    match path.strip_prefix(&self.path_prefix) {
+      Some(rest) => rest.is_empty() || rest.starts_with('/'),
+      None => false,
+    }
+  }
+
+  /// How specific the pattern is, used to pick the best match.
+  fn specificity(&self) -> (bool, usize, usize) {
+    (!self.is_wildcard, self.path_prefix.len(), self.host.len())
+  }
+}
+
+/// Gets the value of the most specific pattern that matches the specifier.
+pub fn find_host_pattern_match<'a, T>(
+  patterns: impl Iterator<Item = (&'a HostPattern, T)>,
+  specifier: &ModuleSpecifier,
+) -> Option<T> {
+  patterns
+    .filter(|(pattern, _)| pattern.matches(specifier))
+    .max_by_key(|(pattern, _)| pattern.specificity())
+    .map(|(_, value)| value)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn matches_host_patterns() {
+    fn matches(pattern: &str, specifier: &str) -> bool {
+      HostPattern::new(pattern)
+        .matches(&ModuleSpecifier::parse(specifier).unwrap())
+    }
+
+    assert!(matches("deno.land", "https://deno.land/x/mod.ts"));
+    assert!(matches("Deno.Land", "https://deno.land/x/mod.ts"));
+    assert!(!matches("deno.land", "https://cdn.deno.land/x/mod.ts"));
+    assert!(matches("deno.land/std", "https://deno.land/std/mod.ts"));
+    assert!(matches("deno.land/std/", "https://deno.land/std/mod.ts"));
+    assert!(!matches("deno.land/std", "https://deno.land/std2/mod.ts"));
+    assert!(!matches("deno.land/std", "https://deno.land/x/mod.ts"));
+    assert!(matches("*.example.com", "https://cdn.example.com/mod.ts"));
+    assert!(!matches("*.example.com", "https://example.com/mod.ts"));
+    assert!(!matches("*.example.com", "https://badexample.com/mod.ts"));
+    assert!(matches("localhost:8000", "http://localhost:8000/mod.ts"));
+    assert!(!matches("localhost:8000", "http://localhost:8001/mod.ts"));
+    assert!(!matches("localhost", "file:///localhost/mod.ts"));
+  }
+
+  #[test]
+  fn finds_most_specific_match() {
+    let patterns = vec![
+      (HostPattern::new("*.land"), 1),
+      (HostPattern::new("deno.land"), 2),
+      (HostPattern::new("deno.land/std"), 3),
+    ];
+    let find = |specifier: &str| {
+      find_host_pattern_match(
+        patterns.iter().map(|(p, v)| (p, *v)),
+        &ModuleSpecifier::parse(specifier).unwrap(),
+      )
+    };
+    assert_eq!(find("https://deno.land/std/mod.ts"), Some(3));
+    assert_eq!(find("https://deno.land/x/mod.ts"), Some(2));
+    assert_eq!(find("https://other.land/x/mod.ts"), Some(1));
+    assert_eq!(find("https://localhost/mod.ts"), None);
+  }
+}
@@ -0,0 +1,139 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::fmt::Write;
+
+use anyhow::bail;
+use anyhow::Result;
+use sha2::Digest;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrityAlgorithm {
+  Sha256,
+  Sha384,
+  Sha512,
+}
+
+impl IntegrityAlgorithm {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      IntegrityAlgorithm::Sha256 => "sha256",
+      IntegrityAlgorithm::Sha384 => "sha384",
+      IntegrityAlgorithm::Sha512 => "sha512",
+    }
+  }
+
+  fn digest(&self, bytes: &[u8]) -> Vec<u8> {
+    match self {
+      IntegrityAlgorithm::Sha256 => sha2::Sha256::digest(bytes).to_vec(),
+      IntegrityAlgorithm::Sha384 => sha2::Sha384::digest(bytes).to_vec(),
+      IntegrityAlgorithm::Sha512 => sha2::Sha512::digest(bytes).to_vec(),
+    }
+  }
+}
+
+/// An expected digest of a module's content in the subresource integrity
+/// format (ex. `sha512-<base64 digest>`). The digest may also be hex encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Integrity {
+  pub algorithm: IntegrityAlgorithm,
+  pub digest: String,
+}
+
+impl Integrity {
+  pub fn parse(text: &str) -> Result<Self> {
+    let Some((prefix, digest)) = text.split_once('-') else {
+      bail!(
+        "Integrity value '{}' must be prefixed with an algorithm (ex. 'sha256-').",
+        text
+      );
+    };
+    let algorithm = match prefix.to_lowercase().as_str() {
+      "sha256" => IntegrityAlgorithm::Sha256,
+      "sha384" => IntegrityAlgorithm::Sha384,
+      "sha512" => IntegrityAlgorithm::Sha512,
+      _ => bail!(
+        "Unsupported integrity algorithm '{}' in '{}'. Expected sha256, sha384, or sha512.",
+        prefix,
+        text
+      ),
+    };
+    Ok(Self {
+      algorithm,
+      digest: digest.to_string(),
+    })
+  }
+
+  pub fn check(&self, bytes: &[u8]) -> Result<()> {
+    let digest = self.algorithm.digest(bytes);
+    let base64_digest = base64::encode(&digest);
+    if self.digest == base64_digest
+      || self.digest.to_lowercase() == to_hex(&digest)
+    {
+      Ok(())
+    } else {
+      bail!(
+        "Integrity check failed.\n\nActual: {}-{}\nExpected: {}-{}",
+        self.algorithm.as_str(),
+        base64_digest,
+        self.algorithm.as_str(),
+        self.digest,
+      )
+    }
+  }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+  let mut text = String::with_capacity(bytes.len() * 2);
+  for byte in bytes {
+    write!(text, "{:02x}", byte).unwrap();
+  }
+  text
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn parses_and_checks_integrity() {
+    let sha256 =
+      Integrity::parse("sha256-LCa0a2j/xo/5m0U8HTBBNBNCLXBkg7+g+YpeiGJm564=")
+        .unwrap();
+    assert_eq!(sha256.algorithm, IntegrityAlgorithm::Sha256);
+    assert!(sha256.check(b"foo").is_ok());
+    assert!(sha256.check(b"bar").is_err());
+
+    let sha256_hex = Integrity::parse(
+      "sha256-2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae",
+    )
+    .unwrap();
+    assert!(sha256_hex.check(b"foo").is_ok());
+
+    let sha384 = Integrity::parse(&format!(
+      "sha384-{}",
+      base64::encode(sha2::Sha384::digest(b"foo"))
+    ))
+    .unwrap();
+    assert!(sha384.check(b"foo").is_ok());
+
+    let sha512 = Integrity::parse(&format!(
+      "sha512-{}",
+      base64::encode(sha2::Sha512::digest(b"foo"))
+    ))
+    .unwrap();
+    assert!(sha512.check(b"foo").is_ok());
+    assert!(sha512.check(b"bar").is_err());
+  }
+
+  #[test]
+  fn errors_unknown_algorithm() {
+    assert_eq!(
+      Integrity::parse("md5-abc").err().unwrap().to_string(),
+      "Unsupported integrity algorithm 'md5' in 'md5-abc'. Expected sha256, sha384, or sha512.",
+    );
+    assert_eq!(
+      Integrity::parse("abc").err().unwrap().to_string(),
+      "Integrity value 'abc' must be prefixed with an algorithm (ex. 'sha256-').",
+    );
+  }
+}
@@ -0,0 +1,146 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::collections::HashMap;
+
+use anyhow::bail;
+use anyhow::Result;
+use deno_semver::package::PackageNv;
+use deno_semver::package::PackageReq;
+use deno_semver::Version;
+use serde_json::Value;
+
+/// Where the version of a package without a version requirement in
+/// its specifier (ex. `npm:chalk`) came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageVersionSource {
+  /// The version was locked in the lockfile.
+  Lockfile,
+  /// The version was not found in a lockfile, so the default was used.
+  Default,
+}
+
+/// Package versions from a Deno lockfile.
+#[derive(Debug, Default, Clone)]
+pub struct Lockfile {
+  /// Package requirements (ex. `npm:chalk` or `jsr:@std/path@^1`) to the
+  /// version they're locked to.
+  versions: HashMap<String, String>,
+}
+
+impl Lockfile {
+  /// Parses the text of a version 3 or 4 lockfile.
+  pub fn parse(text: &str) -> Result<Self> {
+    let value: Value = serde_json::from_str(text)?;
+    let specifiers = match value.get("version").and_then(|v| v.as_str()) {
+      Some("3") => value.get("packages").and_then(|p| p.get("specifiers")),
+      Some("4") => value.get("specifiers"),
+      Some(version) => bail!("Unsupported lockfile version '{}'.", version),
+      None => bail!("Lockfile is missing a version."),
+    };
+    let mut versions = HashMap::new();
+    let specifiers = specifiers.and_then(|s| s.as_object());
+    for (key, value) in specifiers.into_iter().flatten() {
+      let (Some((kind, req)), Some(value)) =
+        (key.split_once(':'), value.as_str())
+      else {
+        continue;
+      };
+      let Ok(req) = PackageReq::from_str(req) else {
+        continue;
+      };
+      // strip the peer dependency information
+      let value = value.split('_').next().unwrap();
+      // version 3 values are the resolved specifier (ex. `npm:chalk@5.3.0`)
+      let version = match value.split_once(':') {
+        Some((_, nv)) => match nv.rsplit_once('@') {
+          Some((_, version)) => version,
+          None => continue,
+        },
+        None => value,
+      };
+      versions.insert(package_key(kind, &req), version.to_string());
+    }
+    Ok(Self { versions })
+  }
+
+  /// Gets the locked version of an npm package requirement.
+  pub fn npm_version(&self, req: &PackageReq) -> Option<&str> {
+    self
+      .versions
+      .get(&package_key("npm", req))
+      .map(|v| v.as_str())
+  }
+
+  /// Gets the locked JSR packages.
+  pub fn jsr_packages(&self) -> Vec<(PackageReq, PackageNv)> {
+    self
+      .versions
+      .iter()
+      .filter_map(|(key, version)| {
+        let req = PackageReq::from_str(key.strip_prefix("jsr:")?).ok()?;
+        let nv = PackageNv {
+          name: req.name.clone(),
+          version: Version::parse_standard(version).ok()?,
+        };
+        Some((req, nv))
+      })
+      .collect()
+  }
+}
+
+fn package_key(kind: &str, req: &PackageReq) -> String {
+  // the display of a package requirement excludes wildcard versions
+  format!("{}:{}", kind, req)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn parses_lockfiles() {
+    let v3 = Lockfile::parse(
+      r#"{
+        "version": "3",
+        "packages": {
+          "specifiers": {
+            "npm:chalk": "npm:chalk@5.3.0",
+            "npm:preact@^10": "npm:preact@10.19.6_other@1.0.0",
+            "jsr:@std/path@^0.220": "jsr:@std/path@0.220.1"
+          }
+        }
+      }"#,
+    )
+    .unwrap();
+    let v4 = Lockfile::parse(
+      r#"{
+        "version": "4",
+        "specifiers": {
+          "npm:chalk@*": "5.3.0",
+          "npm:preact@^10": "10.19.6_other@1.0.0",
+          "jsr:@std/path@^0.220": "0.220.1"
+        }
+      }"#,
+    )
+    .unwrap();
+    for lockfile in [v3, v4] {
+      let chalk = PackageReq::from_str("chalk").unwrap();
+      assert_eq!(lockfile.npm_version(&chalk), Some("5.3.0"));
+      let preact = PackageReq::from_str("preact@^10").unwrap();
+      assert_eq!(lockfile.npm_version(&preact), Some("10.19.6"));
+      let other = PackageReq::from_str("other").unwrap();
+      assert_eq!(lockfile.npm_version(&other), None);
+      let jsr_packages = lockfile.jsr_packages();
+      assert_eq!(jsr_packages.len(), 1);
+      assert_eq!(jsr_packages[0].0.to_string(), "@std/path@^0.220");
+      assert_eq!(jsr_packages[0].1.to_string(), "@std/path@0.220.1");
+    }
+    assert_eq!(
+      Lockfile::parse(r#"{ "version": "2" }"#)
+        .err()
+        .unwrap()
+        .to_string(),
+      "Unsupported lockfile version '2'."
+    );
+  }
+}
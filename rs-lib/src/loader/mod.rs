@@ -1,12 +1,19 @@
 // Copyright 2018-2024 the Deno authors. MIT license.
 
+use std::cell::Cell;
+use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::pin::Pin;
 use std::rc::Rc;
 
+use anyhow::bail;
+use anyhow::Context;
 use anyhow::Result;
+use deno_ast::MediaType;
 use deno_ast::ModuleSpecifier;
+use deno_graph::source::resolve_media_type_and_charset_from_headers;
 use deno_graph::source::CacheSetting;
 use deno_graph::source::LoaderChecksum;
 use futures::future;
@@ -14,22 +21,43 @@ use futures::Future;
 
 #[cfg(feature = "tokio-loader")]
 mod default_loader;
+mod host_pattern;
+mod integrity;
+mod lockfile;
+mod shared_cache_loader;
 mod specifier_mappers;
+mod vendor;
+#[cfg(feature = "vfs-loader")]
+mod vfs_loader;
 
 #[cfg(feature = "tokio-loader")]
 pub use default_loader::*;
+pub use host_pattern::*;
+pub use integrity::*;
+pub use lockfile::*;
+pub use shared_cache_loader::*;
 pub use specifier_mappers::*;
+pub use vendor::*;
+#[cfg(feature = "vfs-loader")]
+pub use vfs_loader::*;
 
+use crate::analyze::get_css_url_references;
+use crate::utils::canonicalize_json;
 use crate::MappedSpecifier;
 use crate::PackageMappedSpecifier;
 
 #[cfg_attr(feature = "serialization", derive(serde::Deserialize))]
 #[cfg_attr(feature = "serialization", serde(rename_all = "camelCase"))]
+#[derive(Clone)]
 pub struct LoadResponse {
   /// The resolved specifier after re-directs.
   pub specifier: ModuleSpecifier,
   pub headers: Option<HashMap<String, String>>,
   pub content: Vec<u8>,
+  /// If the content was served from a cache rather than loaded from
+  /// its source.
+  #[cfg_attr(feature = "serialization", serde(default))]
+  pub from_cache: bool,
 }
 
 pub trait Loader {
@@ -39,12 +67,75 @@ pub trait Loader {
     cache_setting: CacheSetting,
     maybe_checksum: Option<LoaderChecksum>,
   ) -> Pin<Box<dyn Future<Output = Result<Option<LoadResponse>>> + 'static>>;
+
+  /// Gets the names of the entries in a `file:` directory, which is used
+  /// to find the casing of files on disk. Returns `None` when the loader
+  /// can't list the directory.
+  fn list_dir(&self, _dir: &ModuleSpecifier) -> Option<Vec<String>> {
+    None
+  }
 }
 
+/// Callback for observing a redirect from one specifier to another, which
+/// may reject the redirect by returning an error.
+pub type RedirectCallback =
+  dyn Fn(&ModuleSpecifier, &ModuleSpecifier) -> Result<()>;
+
+/// Callback for rewriting the parsed value of a loaded JSON module.
+pub type JsonTransform =
+  dyn Fn(&ModuleSpecifier, serde_json::Value) -> serde_json::Value;
+
 #[derive(Debug, Default, Clone)]
 pub struct LoaderSpecifiers {
   pub mapped_packages: BTreeMap<ModuleSpecifier, PackageMappedSpecifier>,
   pub mapped_modules: HashMap<ModuleSpecifier, ModuleSpecifier>,
+  pub package_version_sources: BTreeMap<ModuleSpecifier, PackageVersionSource>,
+}
+
+/// Statistics about how many loaded modules were served from a cache.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+  pub hits: usize,
+  pub misses: usize,
+  pub bytes_served_from_cache: usize,
+  /// Bytes of the modules that weren't served from a cache.
+  pub bytes_loaded: usize,
+}
+
+/// A module source that isn't valid UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodingError {
+  /// Byte offset of the first invalid byte, including any BOM.
+  pub offset: usize,
+}
+
+impl std::fmt::Display for EncodingError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "Invalid UTF-8 at byte offset {}.", self.offset)
+  }
+}
+
+impl std::error::Error for EncodingError {}
+
+/// Additional settings for how the `SourceLoader` loads modules.
+pub struct SourceLoaderOptions {
+  pub max_total_bytes: Option<usize>,
+  pub integrity: HashMap<ModuleSpecifier, Integrity>,
+  pub default_media_type: Option<MediaType>,
+  pub cache_policy: HashMap<HostPattern, CacheSetting>,
+  pub cache_setting: CacheSetting,
+  pub case_insensitive_file_specifiers: bool,
+  pub known_redirects: BTreeMap<ModuleSpecifier, ModuleSpecifier>,
+  pub module_extensions: HashSet<String>,
+  pub max_modules: Option<usize>,
+  pub lockfile: Option<Lockfile>,
+  pub default_package_version: Option<String>,
+  pub vendor_dir: Option<VendorDir>,
+  pub json_transform: Option<Box<JsonTransform>>,
+  pub platform_suffix: Option<String>,
+  pub index_resolution: bool,
+  pub index_extensions: Vec<String>,
+  pub canonicalize_json: bool,
 }
 
 pub struct SourceLoader<'a> {
@@ -52,6 +143,15 @@ pub struct SourceLoader<'a> {
   specifiers: LoaderSpecifiers,
   specifier_mappers: Vec<Box<dyn SpecifierMapper>>,
   specifier_mappings: &'a HashMap<ModuleSpecifier, MappedSpecifier>,
+  options: Rc<SourceLoaderOptions>,
+  total_bytes: Rc<Cell<usize>>,
+  cache_stats: Rc<Cell<CacheStats>>,
+  warnings: Rc<RefCell<Vec<String>>>,
+  assets: Rc<RefCell<BTreeMap<ModuleSpecifier, Vec<u8>>>>,
+  encoding_errors: Rc<RefCell<BTreeMap<ModuleSpecifier, EncodingError>>>,
+  module_count: usize,
+  /// Lowercase file specifiers to the casing they were first loaded with.
+  file_specifiers: HashMap<String, ModuleSpecifier>,
 }
 
 impl<'a> SourceLoader<'a> {
@@ -59,15 +159,150 @@ impl<'a> SourceLoader<'a> {
     loader: Rc<dyn Loader>,
     specifier_mappers: Vec<Box<dyn SpecifierMapper>>,
     specifier_mappings: &'a HashMap<ModuleSpecifier, MappedSpecifier>,
+    options: SourceLoaderOptions,
   ) -> Self {
     Self {
       loader,
       specifiers: Default::default(),
       specifier_mappers,
       specifier_mappings,
+      options: Rc::new(options),
+      total_bytes: Default::default(),
+      cache_stats: Default::default(),
+      warnings: Default::default(),
+      assets: Default::default(),
+      encoding_errors: Default::default(),
+      module_count: 0,
+      file_specifiers: Default::default(),
     }
   }
 
+  /// Takes the warnings that occurred while loading.
+  pub fn take_warnings(&self) -> Vec<String> {
+    self.warnings.take()
+  }
+
+  /// Takes the contents of the files that were loaded as assets.
+  pub fn take_assets(&self) -> BTreeMap<ModuleSpecifier, Vec<u8>> {
+    self.assets.take()
+  }
+
+  /// Takes the modules that were loaded whose source isn't valid UTF-8.
+  pub fn take_encoding_errors(
+    &self,
+  ) -> BTreeMap<ModuleSpecifier, EncodingError> {
+    self.encoding_errors.take()
+  }
+
+  /// Loads the assets referenced by the CSS assets loaded so far via
+  /// `@import` and `url()`, along with the assets those reference. A
+  /// warning is added for references that fail to load.
+  pub async fn load_css_asset_references(&mut self) {
+    let mut pending = self
+      .assets
+      .borrow()
+      .keys()
+      .filter(|s| is_css_specifier(s))
+      .cloned()
+      .collect::<Vec<_>>();
+    let mut visited = pending.iter().cloned().collect::<HashSet<_>>();
+    while let Some(referrer) = pending.pop() {
+      let references = match self.assets.borrow().get(&referrer) {
+        Some(bytes) => get_css_asset_references(&referrer, bytes),
+        None => continue,
+      };
+      for specifier in references {
+        if !visited.insert(specifier.clone()) {
+          continue;
+        }
+        let result = deno_graph::source::Loader::load(
+          self,
+          &specifier,
+          deno_graph::source::LoadOptions {
+            is_dynamic: false,
+            cache_setting: CacheSetting::Use,
+            maybe_checksum: None,
+          },
+        )
+        .await;
+        match result {
+          Ok(Some(deno_graph::source::LoadResponse::External {
+            specifier,
+          })) => {
+            if is_css_specifier(&specifier) {
+              pending.push(specifier);
+            }
+          }
+          Ok(Some(_)) => {}
+          Ok(None) => self.warnings.borrow_mut().push(format!(
+            "Could not find {}, which is referenced by {}.",
+            specifier, referrer,
+          )),
+          Err(err) => self.warnings.borrow_mut().push(format!(
+            "Could not load {}, which is referenced by {}: {:#}",
+            specifier, referrer, err,
+          )),
+        }
+      }
+    }
+  }
+
+  /// Gets the cache hits and misses of the modules loaded so far.
+  pub fn cache_stats(&self) -> CacheStats {
+    self.cache_stats.get()
+  }
+
+  /// Gets if more modules were requested than the maximum allowed.
+  pub fn exceeded_max_modules(&self) -> bool {
+    match self.options.max_modules {
+      Some(max_modules) => self.module_count > max_modules,
+      None => false,
+    }
+  }
+
+  /// The number of modules that were requested to be loaded.
+  pub fn module_count(&self) -> usize {
+    self.module_count
+  }
+
+  /// Sets the version of npm packages without a version requirement
+  /// from the lockfile or otherwise the default version.
+  fn resolve_unversioned_npm_package(
+    &mut self,
+    specifier: &ModuleSpecifier,
+    entry: &mut PackageMappedSpecifier,
+  ) {
+    let Ok(npm_specifier) =
+      deno_semver::npm::NpmPackageReqReference::from_specifier(specifier)
+    else {
+      return;
+    };
+    if npm_specifier.req().version_req.version_text() != "*" {
+      return;
+    }
+    let locked_version = self
+      .options
+      .lockfile
+      .as_ref()
+      .and_then(|lockfile| lockfile.npm_version(npm_specifier.req()));
+    let source = match locked_version {
+      Some(version) => {
+        entry.version = Some(version.to_string());
+        PackageVersionSource::Lockfile
+      }
+      None => {
+        if let Some(version) = &self.options.default_package_version {
+          entry.version = Some(version.clone());
+        }
+        PackageVersionSource::Default
+      }
+    };
+    self
+      .specifiers
+      .package_version_sources
+      .insert(specifier.clone(), source);
+  }
+
   pub fn into_specifiers(self) -> LoaderSpecifiers {
     self.specifiers
   }
@@ -79,6 +314,13 @@ impl<'a> deno_graph::source::Loader for SourceLoader<'a> {
     specifier: &ModuleSpecifier,
     load_options: deno_graph::source::LoadOptions,
   ) -> deno_graph::source::LoadFuture {
+    self.module_count += 1;
+    if self.exceeded_max_modules() {
+      // stop expanding the graph
+      return Box::pin(future::ready(Err(anyhow::anyhow!(
+        "Exceeded the maximum number of modules."
+      ))));
+    }
     let specifier = match self.specifier_mappings.get(specifier) {
       Some(MappedSpecifier::Package(mapping)) => {
         self
@@ -97,7 +339,8 @@ impl<'a> deno_graph::source::Loader for SourceLoader<'a> {
       }
       None => {
         for mapper in self.specifier_mappers.iter() {
-          if let Some(entry) = mapper.map(specifier) {
+          if let Some(mut entry) = mapper.map(specifier) {
+            self.resolve_unversioned_npm_package(specifier, &mut entry);
             self
               .specifiers
               .mapped_packages
@@ -110,32 +353,416 @@ impl<'a> deno_graph::source::Loader for SourceLoader<'a> {
       }
     };
 
+    let specifier =
+      resolve_known_redirects(&self.options.known_redirects, specifier);
     let loader = self.loader.clone();
-    let specifier = specifier.to_owned();
+    let specifier = if self.options.case_insensitive_file_specifiers
+      && specifier.scheme() == "file"
+    {
+      // load with the casing on disk, or the first seen casing when the
+      // loader can't list directories, so the graph records a redirect to
+      // that module instead of creating a duplicate
+      self
+        .file_specifiers
+        .entry(specifier.as_str().to_lowercase())
+        .or_insert_with(|| resolve_file_casing(&*loader, specifier))
+        .clone()
+    } else {
+      specifier.to_owned()
+    };
+    let cache_setting = get_cache_setting(
+      &self.options.cache_policy,
+      self.options.cache_setting,
+      load_options.cache_setting,
+      &specifier,
+    );
+    let vendored_specifier = match &self.options.vendor_dir {
+      Some(vendor_dir) if matches!(specifier.scheme(), "http" | "https") => {
+        match vendor_dir.resolve(&specifier) {
+          Some(local_specifier) => Some(local_specifier),
+          None => {
+            return Box::pin(future::ready(Err(anyhow::anyhow!(
+              "{} was not found in the vendor directory.",
+              specifier
+            ))))
+          }
+        }
+      }
+      _ => None,
+    };
+    let options = self.options.clone();
+    let total_bytes = self.total_bytes.clone();
+    let cache_stats = self.cache_stats.clone();
+    let warnings = self.warnings.clone();
+    let assets = self.assets.clone();
+    let encoding_errors = self.encoding_errors.clone();
     Box::pin(async move {
       if specifier.scheme() == "node" {
         return Ok(Some(deno_graph::source::LoadResponse::External {
           specifier,
         }));
       }
-      let resp = loader
-        .load(
-          specifier.clone(),
-          load_options.cache_setting,
-          load_options.maybe_checksum,
-        )
-        .await;
-      resp.map(|r| {
-        r.map(|r| deno_graph::source::LoadResponse::Module {
+      let platform_specifier = options
+        .platform_suffix
+        .as_ref()
+        .and_then(|suffix| get_platform_specifier(&specifier, suffix));
+      let platform_resp = match &platform_specifier {
+        // loaders may error rather than return nothing for a missing file,
+        // so any failure falls back to the base file
+        Some(platform_specifier) => loader
+          .load(platform_specifier.clone(), cache_setting, None)
+          .await
+          .ok()
+          .flatten(),
+        None => None,
+      };
+      // the integrity of the platform file is checked when it's served
+      let integrity_specifier = match (&platform_resp, platform_specifier) {
+        (Some(_), Some(platform_specifier)) => platform_specifier,
+        _ => specifier.clone(),
+      };
+      let resp = match platform_resp {
+        // the graph will record a redirect to the platform file
+        Some(resp) => Some(resp),
+        None => {
+          let mut resp = loader
+            .load(
+              vendored_specifier
+                .clone()
+                .unwrap_or_else(|| specifier.clone()),
+              cache_setting,
+              load_options.maybe_checksum,
+            )
+            .await;
+          // a directory may fail to load rather than not be found
+          if options.index_resolution && !matches!(resp, Ok(Some(_))) {
+            if let Some(index_resp) = load_index_module(
+              loader.as_ref(),
+              &specifier,
+              &options.index_extensions,
+              cache_setting,
+            )
+            .await
+            {
+              resp = Ok(Some(index_resp));
+            }
+          }
+          resp?
+        }
+      };
+      // keep the remote specifier in the graph, but use the media type
+      // of the local file since it may have been given an extension
+      let resp = match vendored_specifier {
+        Some(local_specifier) => resp.map(|r| LoadResponse {
+          specifier: specifier.clone(),
+          headers: MediaType::from_specifier(&local_specifier)
+            .as_content_type()
+            .map(|content_type| {
+              HashMap::from([(
+                "content-type".to_string(),
+                content_type.to_string(),
+              )])
+            }),
+          content: r.content,
+          from_cache: r.from_cache,
+        }),
+        None => resp,
+      };
+      if let Some(r) = &resp {
+        let mut stats = cache_stats.get();
+        if r.from_cache {
+          stats.hits += 1;
+          stats.bytes_served_from_cache += r.content.len();
+        } else {
+          stats.misses += 1;
+          stats.bytes_loaded += r.content.len();
+        }
+        cache_stats.set(stats);
+        if let Some(integrity) = options.integrity.get(&integrity_specifier) {
+          integrity.check(&r.content).with_context(|| {
+            format!("Failed integrity check for {}", integrity_specifier)
+          })?;
+        }
+        total_bytes.set(total_bytes.get() + r.content.len());
+        if let Some(max_total_bytes) = options.max_total_bytes {
+          if total_bytes.get() > max_total_bytes {
+            bail!(
+              "download budget exceeded ({} bytes loaded, maximum is {} bytes)",
+              total_bytes.get(),
+              max_total_bytes,
+            );
+          }
+        }
+        if is_asset(
+          &r.specifier,
+          r.headers.as_ref(),
+          &options.module_extensions,
+          options.default_media_type,
+        ) {
+          let specifier = r.specifier.clone();
+          assets
+            .borrow_mut()
+            .insert(specifier.clone(), r.content.clone());
+          return Ok(Some(deno_graph::source::LoadResponse::External {
+            specifier,
+          }));
+        }
+      }
+      Ok(resp.map(|mut r| {
+        const BOM: &[u8] = b"\xEF\xBB\xBF";
+        let bom_len = if r.content.starts_with(BOM) { BOM.len() } else { 0 };
+        if let Err(err) = std::str::from_utf8(&r.content[bom_len..]) {
+          encoding_errors.borrow_mut().insert(
+            r.specifier.clone(),
+            EncodingError {
+              offset: bom_len + err.valid_up_to(),
+            },
+          );
+        }
+        let (media_type, _) = resolve_media_type_and_charset_from_headers(
+          &r.specifier,
+          r.headers.as_ref(),
+        );
+        if let Some(json_transform) = &options.json_transform {
+          // leave invalid json as-is to be handled like any other json module
+          if media_type == MediaType::Json {
+            if let Ok(value) = serde_json::from_slice(&r.content) {
+              let value = json_transform(&r.specifier, value);
+              r.content = serde_json::to_vec_pretty(&value).unwrap();
+            }
+          }
+        }
+        if options.canonicalize_json && media_type == MediaType::Json {
+          // leave invalid json as-is to be handled like any other json module
+          if let Some(text) = std::str::from_utf8(&r.content)
+            .ok()
+            .and_then(canonicalize_json)
+          {
+            r.content = text.into_bytes();
+          }
+        }
+        if let Some(default_media_type) = options.default_media_type {
+          if media_type == MediaType::Unknown {
+            if let Some(content_type) = default_media_type.as_content_type() {
+              warnings.borrow_mut().push(format!(
+                "Could not determine the media type of {}, so it was treated as {}.",
+                r.specifier, default_media_type,
+              ));
+              r.headers
+                .get_or_insert_with(Default::default)
+                .insert("content-type".to_string(), content_type.to_string());
+            }
+          }
+        }
+        deno_graph::source::LoadResponse::Module {
           specifier: r.specifier,
           content: r.content.into(),
           maybe_headers: r.headers,
-        })
-      })
+        }
+      }))
     })
   }
 }
 
+/// Loads the index module in the directory of an extensionless file
+/// specifier (ex. `./utils/index.ts` for `./utils`), trying each of the
+/// extensions in order. An error loading an index file is treated as it
+/// not existing, since loaders may error rather than return nothing for a
+/// missing file.
+async fn load_index_module(
+  loader: &dyn Loader,
+  specifier: &ModuleSpecifier,
+  extensions: &[String],
+  cache_setting: CacheSetting,
+) -> Option<LoadResponse> {
+  if specifier.scheme() != "file" {
+    return None;
+  }
+  let file_name = specifier.path().rsplit('/').next().unwrap_or("");
+  if file_name.contains('.') {
+    return None;
+  }
+  for ext in extensions {
+    let index_specifier = if file_name.is_empty() {
+      specifier.join(&format!("index.{}", ext))
+    } else {
+      specifier.join(&format!("{}/index.{}", file_name, ext))
+    };
+    let Ok(index_specifier) = index_specifier else {
+      continue;
+    };
+    if let Ok(Some(resp)) =
+      loader.load(index_specifier, cache_setting, None).await
+    {
+      return Some(resp);
+    }
+  }
+  None
+}
+
+/// Gets the platform specific variant of a file specifier (ex. `x.node.ts`
+/// for `x.ts` or `x.node.d.ts` for `x.d.ts`).
+fn get_platform_specifier(
+  specifier: &ModuleSpecifier,
+  suffix: &str,
+) -> Option<ModuleSpecifier> {
+  if specifier.scheme() != "file" {
+    return None;
+  }
+  let path = specifier.path();
+  let file_name = path.rsplit('/').next()?;
+  let lowercase_file_name = file_name.to_lowercase();
+  let ext_len = [".d.ts", ".d.mts", ".d.cts"]
+    .iter()
+    .find(|ext| lowercase_file_name.ends_with(*ext))
+    .map(|ext| ext.len())
+    .or_else(|| file_name.rfind('.').map(|index| file_name.len() - index))?;
+  let (stem, ext) = file_name.split_at(file_name.len() - ext_len);
+  if stem.is_empty() || stem.ends_with(&format!(".{}", suffix)) {
+    return None;
+  }
+  let mut platform_specifier = specifier.clone();
+  platform_specifier.set_path(&format!(
+    "{}{}.{}{}",
+    &path[..path.len() - file_name.len()],
+    stem,
+    suffix,
+    ext
+  ));
+  Some(platform_specifier)
+}
+
+/// Gets the file specifier with the casing of each path segment on disk
+/// according to the loader's directory listings. Segments that can't be
+/// found keep their casing.
+fn resolve_file_casing(
+  loader: &dyn Loader,
+  specifier: &ModuleSpecifier,
+) -> ModuleSpecifier {
+  let Some(segments) = specifier.path_segments() else {
+    return specifier.clone();
+  };
+  let mut dir = specifier.clone();
+  dir.set_path("/");
+  dir.set_query(None);
+  dir.set_fragment(None);
+  let mut path = String::new();
+  for segment in segments {
+    let entries = loader.list_dir(&dir).unwrap_or_default();
+    let name = entries
+      .iter()
+      .find(|name| *name == segment)
+      .or_else(|| {
+        let segment = segment.to_lowercase();
+        entries.iter().find(|name| name.to_lowercase() == segment)
+      })
+      .map(|name| name.as_str())
+      .unwrap_or(segment);
+    path.push('/');
+    path.push_str(name);
+    dir.set_path(&format!("{}/", path));
+  }
+  let mut resolved = specifier.clone();
+  resolved.set_path(&path);
+  resolved
+}
+
+/// Gets the specifiers of the files referenced by a CSS asset via
+/// `@import` and `url()`.
+pub fn get_css_asset_references(
+  specifier: &ModuleSpecifier,
+  bytes: &[u8],
+) -> Vec<ModuleSpecifier> {
+  let Ok(text) = std::str::from_utf8(bytes) else {
+    return Vec::new();
+  };
+  get_css_url_references(text)
+    .into_iter()
+    .filter_map(|value| specifier.join(&value).ok())
+    .collect()
+}
+
+fn is_css_specifier(specifier: &ModuleSpecifier) -> bool {
+  specifier.path().to_lowercase().ends_with(".css")
+}
+
+/// Gets if the specifier has an extension that's not a module extension
+/// and its media type isn't JavaScript, TypeScript, or JSON. The media type
+/// is checked first so that extensionless urls with a version (ex.
+/// `https://esm.sh/preact@10.19.0`) aren't classified by the text after the
+/// last period. Files with an unknown media type are modules rather than
+/// assets when there's a default media type to treat them as.
+fn is_asset(
+  specifier: &ModuleSpecifier,
+  headers: Option<&HashMap<String, String>>,
+  module_extensions: &HashSet<String>,
+  default_media_type: Option<MediaType>,
+) -> bool {
+  if module_extensions.is_empty() {
+    return false;
+  }
+  let (media_type, _) =
+    resolve_media_type_and_charset_from_headers(specifier, headers);
+  match media_type {
+    MediaType::JavaScript
+    | MediaType::Jsx
+    | MediaType::Mjs
+    | MediaType::Cjs
+    | MediaType::TypeScript
+    | MediaType::Mts
+    | MediaType::Cts
+    | MediaType::Dts
+    | MediaType::Dmts
+    | MediaType::Dcts
+    | MediaType::Tsx
+    | MediaType::Json => return false,
+    MediaType::Unknown if default_media_type.is_some() => return false,
+    MediaType::Wasm
+    | MediaType::TsBuildInfo
+    | MediaType::SourceMap
+    | MediaType::Unknown => {}
+  }
+  let file_name = specifier.path().rsplit('/').next().unwrap_or("");
+  match file_name.rsplit_once('.') {
+    Some((_, ext)) => !module_extensions.contains(&ext.to_lowercase()),
+    None => false,
+  }
+}
+
+/// Gets the cache setting of the most specific cache policy that matches
+/// the specifier, otherwise the default cache setting for remote
+/// specifiers. The requested setting is kept for local files and when the
+/// default is `CacheSetting::Use`.
+pub fn get_cache_setting(
+  cache_policy: &HashMap<HostPattern, CacheSetting>,
+  default: CacheSetting,
+  requested: CacheSetting,
+  specifier: &ModuleSpecifier,
+) -> CacheSetting {
+  find_host_pattern_match(cache_policy.iter().map(|(p, s)| (p, *s)), specifier)
+    .unwrap_or(
+      if specifier.scheme() == "file" || default == CacheSetting::Use {
+        requested
+      } else {
+        default
+      },
+    )
+}
+
+fn resolve_known_redirects<'a>(
+  known_redirects: &'a BTreeMap<ModuleSpecifier, ModuleSpecifier>,
+  mut specifier: &'a ModuleSpecifier,
+) -> &'a ModuleSpecifier {
+  // limit the number of hops in case there's a cycle
+  for _ in 0..known_redirects.len() {
+    match known_redirects.get(specifier) {
+      Some(redirect) => specifier = redirect,
+      None => break,
+    }
+  }
+  specifier
+}
+
 fn get_dummy_module(
   specifier: &ModuleSpecifier,
 ) -> deno_graph::source::LoadFuture {
@@ -0,0 +1,100 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use anyhow::anyhow;
+use anyhow::Result;
+use deno_ast::ModuleSpecifier;
+use deno_graph::source::CacheSetting;
+use deno_graph::source::LoaderChecksum;
+use futures::future::LocalBoxFuture;
+use futures::future::Shared;
+use futures::Future;
+use futures::FutureExt;
+
+use crate::LoadResponse;
+use crate::Loader;
+
+type SharedLoad = Shared<
+  LocalBoxFuture<'static, Result<Option<LoadResponse>, Rc<anyhow::Error>>>,
+>;
+
+/// The loads of remote modules, including those still in progress, so
+/// concurrent requests for the same module share a single download.
+pub type LoaderCache = Rc<RefCell<HashMap<ModuleSpecifier, SharedLoad>>>;
+
+/// Loader that stores the responses of remote modules in a cache that
+/// may be shared with other loaders so each is only downloaded once.
+pub struct SharedCacheLoader {
+  loader: Rc<dyn Loader>,
+  cache: LoaderCache,
+}
+
+impl SharedCacheLoader {
+  pub fn new(loader: Rc<dyn Loader>, cache: LoaderCache) -> Self {
+    Self { loader, cache }
+  }
+}
+
+impl Loader for SharedCacheLoader {
+  fn list_dir(&self, dir: &ModuleSpecifier) -> Option<Vec<String>> {
+    self.loader.list_dir(dir)
+  }
+
+  fn load(
+    &self,
+    specifier: ModuleSpecifier,
+    cache_setting: CacheSetting,
+    maybe_checksum: Option<LoaderChecksum>,
+  ) -> Pin<Box<dyn Future<Output = Result<Option<LoadResponse>>> + 'static>> {
+    if !matches!(specifier.scheme(), "http" | "https") {
+      return self.loader.load(specifier, cache_setting, maybe_checksum);
+    }
+    if cache_setting != CacheSetting::Reload {
+      if let Some(shared_load) = self.cache.borrow().get(&specifier).cloned() {
+        return Box::pin(async move {
+          let Some(response) = shared_load.await.map_err(to_error)? else {
+            return Ok(None);
+          };
+          if let Some(checksum) = &maybe_checksum {
+            checksum.check_source(&response.content)?;
+          }
+          Ok(Some(LoadResponse {
+            from_cache: true,
+            ..response
+          }))
+        });
+      }
+    }
+    let response =
+      self
+        .loader
+        .load(specifier.clone(), cache_setting, maybe_checksum);
+    let cache = self.cache.clone();
+    let shared_load = {
+      let specifier = specifier.clone();
+      async move {
+        let response = response.await;
+        // only keep successful loads so failures are retried
+        if !matches!(response, Ok(Some(_))) {
+          cache.borrow_mut().remove(&specifier);
+        }
+        response.map_err(Rc::new)
+      }
+    }
+    .boxed_local()
+    .shared();
+    self
+      .cache
+      .borrow_mut()
+      .insert(specifier, shared_load.clone());
+    Box::pin(async move { shared_load.await.map_err(to_error) })
+  }
+}
+
+fn to_error(err: Rc<anyhow::Error>) -> anyhow::Error {
+  anyhow!("{:#}", err)
+}
@@ -0,0 +1,67 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use anyhow::Result;
+use deno_ast::ModuleSpecifier;
+
+/// Local mirror of remote modules created by `deno vendor`.
+#[derive(Debug)]
+pub struct VendorDir {
+  import_map_url: ModuleSpecifier,
+  import_map: import_map::ImportMap,
+}
+
+impl VendorDir {
+  /// Parses the text of the import map in the vendor directory.
+  pub fn parse(import_map_url: ModuleSpecifier, text: &str) -> Result<Self> {
+    let result = import_map::parse_from_json(&import_map_url, text)?;
+    Ok(Self {
+      import_map_url,
+      import_map: result.import_map,
+    })
+  }
+
+  /// Gets the local file that mirrors the remote specifier.
+  pub fn resolve(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Option<ModuleSpecifier> {
+    self
+      .import_map
+      .resolve(specifier.as_str(), &self.import_map_url)
+      .ok()
+      .filter(|resolved| resolved.scheme() == "file")
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn resolves_vendored_files() {
+    let vendor_dir = VendorDir::parse(
+      ModuleSpecifier::parse("file:///vendor/import_map.json").unwrap(),
+      r#"{
+        "imports": {
+          "https://deno.land/": "./deno.land/",
+          "https://esm.sh/preact": "./esm.sh/preact.js"
+        }
+      }"#,
+    )
+    .unwrap();
+    let resolve = |specifier: &str| {
+      vendor_dir
+        .resolve(&ModuleSpecifier::parse(specifier).unwrap())
+        .map(|s| s.to_string())
+    };
+    assert_eq!(
+      resolve("https://deno.land/std/mod.ts"),
+      Some("file:///vendor/deno.land/std/mod.ts".to_string())
+    );
+    assert_eq!(
+      resolve("https://esm.sh/preact"),
+      Some("file:///vendor/esm.sh/preact.js".to_string())
+    );
+    assert_eq!(resolve("https://example.com/mod.ts"), None);
+  }
+}
@@ -0,0 +1,147 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::pin::Pin;
+
+use anyhow::Result;
+use deno_ast::ModuleSpecifier;
+use deno_graph::source::CacheSetting;
+use deno_graph::source::LoaderChecksum;
+use futures::future;
+use futures::Future;
+
+use crate::utils::url_to_file_path;
+use crate::LoadResponse;
+use crate::Loader;
+
+/// Loads `file:` specifiers from an in-memory file system for
+/// environments without `std::fs` (ex. Wasm). Other specifiers are
+/// not found.
+#[derive(Debug, Default, Clone)]
+pub struct VfsLoader {
+  files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl VfsLoader {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn add_file(
+    &mut self,
+    path: impl Into<PathBuf>,
+    content: impl Into<Vec<u8>>,
+  ) -> &mut Self {
+    self.files.insert(path.into(), content.into());
+    self
+  }
+
+  /// Gets the paths of the files and directories directly within the
+  /// provided directory, sorted by path.
+  pub fn read_dir(&self, dir: impl AsRef<Path>) -> Vec<PathBuf> {
+    let dir = dir.as_ref();
+    self
+      .files
+      .keys()
+      .filter_map(|path| {
+        let relative = path.strip_prefix(dir).ok()?;
+        let name = relative.components().next()?;
+        Some(dir.join(name))
+      })
+      .collect::<BTreeSet<_>>()
+      .into_iter()
+      .collect()
+  }
+}
+
+impl Loader for VfsLoader {
+  fn list_dir(&self, dir: &ModuleSpecifier) -> Option<Vec<String>> {
+    let dir = url_to_file_path(dir).ok()?;
+    Some(
+      self
+        .read_dir(dir)
+        .iter()
+        .filter_map(|path| {
+          Some(path.file_name()?.to_string_lossy().to_string())
+        })
+        .collect(),
+    )
+  }
+
+  fn load(
+    &self,
+    specifier: ModuleSpecifier,
+    _cache_setting: CacheSetting,
+    maybe_checksum: Option<LoaderChecksum>,
+  ) -> Pin<Box<dyn Future<Output = Result<Option<LoadResponse>>> + 'static>> {
+    if specifier.scheme() != "file" {
+      return Box::pin(future::ready(Ok(None)));
+    }
+    let result = url_to_file_path(&specifier).and_then(|file_path| {
+      let Some(content) = self.files.get(&file_path) else {
+        return Ok(None);
+      };
+      if let Some(checksum) = maybe_checksum {
+        checksum.check_source(content)?;
+      }
+      Ok(Some(LoadResponse {
+        specifier,
+        content: content.clone(),
+        headers: None,
+        from_cache: false,
+      }))
+    });
+    Box::pin(future::ready(result))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn loads_files() {
+    let mut loader = VfsLoader::new();
+    loader.add_file("/mod.ts", "export const a = 1;");
+    let load = |specifier: &str| {
+      futures::executor::block_on(loader.load(
+        ModuleSpecifier::parse(specifier).unwrap(),
+        CacheSetting::Use,
+        None,
+      ))
+      .unwrap()
+    };
+    assert_eq!(
+      load("file:///mod.ts").unwrap().content,
+      b"export const a = 1;"
+    );
+    assert!(load("file:///other.ts").is_none());
+    assert!(load("https://example.com/mod.ts").is_none());
+  }
+
+  #[test]
+  fn reads_dir() {
+    let mut loader = VfsLoader::new();
+    loader
+      .add_file("/src/mod.ts", "")
+      .add_file("/src/sub/a.ts", "")
+      .add_file("/src/sub/b.ts", "")
+      .add_file("/other.ts", "");
+    assert_eq!(
+      loader.read_dir("/src"),
+      vec![PathBuf::from("/src/mod.ts"), PathBuf::from("/src/sub")]
+    );
+    assert_eq!(
+      loader.read_dir("/"),
+      vec![PathBuf::from("/other.ts"), PathBuf::from("/src")]
+    );
+    assert!(loader.read_dir("/missing").is_empty());
+    assert_eq!(
+      loader.list_dir(&ModuleSpecifier::parse("file:///src/").unwrap()),
+      Some(vec!["mod.ts".to_string(), "sub".to_string()])
+    );
+  }
+}
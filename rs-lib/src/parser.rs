@@ -1,7 +1,10 @@
 // Copyright 2018-2024 the Deno authors. MIT license.
 
 use anyhow::Result;
+use deno_ast::get_syntax;
 use deno_ast::parse_module;
+use deno_ast::swc::parser::Syntax;
+use deno_ast::MediaType;
 use deno_ast::ParseDiagnostic;
 use deno_ast::ParseParams;
 use deno_ast::ParsedSource;
@@ -9,8 +12,48 @@ use deno_ast::SourceTextInfo;
 use deno_graph::ModuleParser;
 use deno_graph::ParseOptions;
 
+/// Overrides of the syntax modules are parsed with, which otherwise
+/// depends on their media type.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SyntaxOptions {
+  /// Parse JSX in all JavaScript and TypeScript modules instead of only
+  /// `.jsx` and `.tsx` modules (ex. for a codebase that uses JSX with a
+  /// custom pragma in `.js` files). Declaration files are unaffected.
+  pub jsx: bool,
+  /// Whether to parse decorators. Defaults to parsing them.
+  pub decorators: Option<bool>,
+}
+
+impl SyntaxOptions {
+  fn get_syntax(&self, media_type: MediaType) -> Option<Syntax> {
+    if *self == Self::default() {
+      return None;
+    }
+    let mut syntax = get_syntax(media_type);
+    match &mut syntax {
+      Syntax::Typescript(config) => {
+        if self.jsx && !config.dts {
+          config.tsx = true;
+        }
+        if let Some(decorators) = self.decorators {
+          config.decorators = decorators;
+        }
+      }
+      Syntax::Es(config) => {
+        config.jsx |= self.jsx;
+        if let Some(decorators) = self.decorators {
+          config.decorators = decorators;
+        }
+      }
+    }
+    Some(syntax)
+  }
+}
+
 #[derive(Default, Copy, Clone)]
-pub struct ScopeAnalysisParser;
+pub struct ScopeAnalysisParser {
+  pub syntax: SyntaxOptions,
+}
 
 impl ModuleParser for ScopeAnalysisParser {
   fn parse_module(
@@ -23,7 +66,7 @@ impl ModuleParser for ScopeAnalysisParser {
       media_type: options.media_type,
       capture_tokens: true,
       scope_analysis: true,
-      maybe_syntax: None,
+      maybe_syntax: self.syntax.get_syntax(options.media_type),
     })
   }
 }
@@ -13,6 +13,8 @@ use crate::declaration_file_resolution::resolve_declaration_file_mappings;
 use crate::declaration_file_resolution::DeclarationFileResolution;
 use crate::graph::ModuleGraph;
 use crate::loader::LoaderSpecifiers;
+use crate::loader::PackageVersionSource;
+use crate::Dependency;
 use crate::PackageMappedSpecifier;
 
 #[derive(Debug)]
@@ -23,6 +25,9 @@ pub struct Specifiers {
   pub test_modules: HashSet<ModuleSpecifier>,
   pub main: EnvironmentSpecifiers,
   pub test: EnvironmentSpecifiers,
+  type_only_modules: HashSet<ModuleSpecifier>,
+  package_version_sources: BTreeMap<ModuleSpecifier, PackageVersionSource>,
+  types_packages: Vec<Dependency>,
 }
 
 impl Specifiers {
@@ -30,6 +35,28 @@ impl Specifiers {
     self.main.mapped.contains_key(specifier)
       || self.test.mapped.contains_key(specifier)
   }
+
+  /// Local and remote modules that are only reachable via type-only
+  /// dependencies, so they don't need to be emitted as runtime files.
+  pub fn type_only_modules(&self) -> &HashSet<ModuleSpecifier> {
+    &self.type_only_modules
+  }
+
+  /// Where the versions of mapped npm packages without a version in their
+  /// specifier came from, which is useful for warning about unpinned
+  /// dependencies.
+  pub fn package_version_sources(
+    &self,
+  ) -> &BTreeMap<ModuleSpecifier, PackageVersionSource> {
+    &self.package_version_sources
+  }
+
+  /// The `@types/` packages that provide the types of the mapped packages
+  /// (ex. `@types/lodash` for `lodash`). These are only needed for type
+  /// checking, so should be dev dependencies.
+  pub fn types_packages(&self) -> &[Dependency] {
+    &self.types_packages
+  }
 }
 
 #[derive(Debug)]
@@ -37,9 +64,25 @@ pub struct EnvironmentSpecifiers {
   pub mapped: BTreeMap<ModuleSpecifier, PackageMappedSpecifier>,
 }
 
+impl EnvironmentSpecifiers {
+  /// Gets the module specifier text that each mapped module will be
+  /// imported with in the output.
+  pub fn output_specifiers(&self) -> BTreeMap<ModuleSpecifier, String> {
+    self
+      .mapped
+      .iter()
+      .map(|(specifier, mapped)| {
+        (specifier.clone(), mapped.module_specifier_text())
+      })
+      .collect()
+  }
+}
+
 pub fn get_specifiers<'a>(
   entry_points: &[ModuleSpecifier],
+  test_entry_points: &[ModuleSpecifier],
   mut specifiers: LoaderSpecifiers,
+  types_package_versions: &HashMap<String, String>,
   module_graph: &ModuleGraph,
   modules: impl Iterator<Item = &'a Module>,
 ) -> Result<Specifiers> {
@@ -137,11 +180,39 @@ pub fn get_specifiers<'a>(
     }
   }
 
+  let runtime_specifiers = get_runtime_specifiers(
+    module_graph,
+    entry_points.iter().chain(test_entry_points.iter()),
+  );
+  let type_only_modules = local_specifiers
+    .iter()
+    .chain(remote_specifiers.iter())
+    .filter(|s| {
+      !runtime_specifiers.contains(s) && !declaration_specifiers.contains(s)
+    })
+    .cloned()
+    .collect();
+
   ensure_package_mapped_specifiers_valid(
     &found_mapped_specifiers,
     &specifiers.mapped_packages,
   )?;
 
+  let mut types_packages = found_mapped_specifiers
+    .values()
+    .chain(specifiers.mapped_packages.values())
+    .filter_map(|mapped| {
+      let version = types_package_versions.get(&mapped.name)?;
+      Some(Dependency {
+        name: get_types_package_name(&mapped.name),
+        version: version.clone(),
+        peer_dependency: false,
+      })
+    })
+    .collect::<Vec<_>>();
+  types_packages.sort_by(|a, b| a.name.cmp(&b.name));
+  types_packages.dedup();
+
   Ok(Specifiers {
     local: local_specifiers
       .into_iter()
@@ -162,9 +233,44 @@ pub fn get_specifiers<'a>(
     test: EnvironmentSpecifiers {
       mapped: specifiers.mapped_packages,
     },
+    type_only_modules,
+    package_version_sources: specifiers.package_version_sources,
+    types_packages,
   })
 }
 
+/// Gets the DefinitelyTyped package name for a package
+/// (ex. `@scope/pkg` to `@types/scope__pkg`).
+fn get_types_package_name(name: &str) -> String {
+  match name.strip_prefix('@') {
+    Some(scoped_name) => format!("@types/{}", scoped_name.replace('/', "__")),
+    None => format!("@types/{}", name),
+  }
+}
+
+/// Gets the modules reachable from the roots while only following
+/// dependencies that are used at runtime.
+fn get_runtime_specifiers<'a>(
+  module_graph: &ModuleGraph,
+  roots: impl Iterator<Item = &'a ModuleSpecifier>,
+) -> HashSet<ModuleSpecifier> {
+  let mut found = HashSet::new();
+  let mut pending = roots.map(|s| module_graph.resolve(s)).collect::<Vec<_>>();
+  while let Some(specifier) = pending.pop() {
+    if !found.insert(specifier.clone()) {
+      continue;
+    }
+    if let Some(module) = module_graph.get(&specifier).js() {
+      for dep in module.dependencies.values() {
+        if let Some(code_specifier) = dep.get_code() {
+          pending.push(module_graph.resolve(code_specifier));
+        }
+      }
+    }
+  }
+  found
+}
+
 fn ensure_package_mapped_specifiers_valid(
   mapped_specifiers: &BTreeMap<ModuleSpecifier, PackageMappedSpecifier>,
   test_mapped_specifiers: &BTreeMap<ModuleSpecifier, PackageMappedSpecifier>,
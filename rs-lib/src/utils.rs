@@ -165,6 +165,101 @@ pub fn partition_by_root_specifiers<'a>(
   root_specifiers
 }
 
+/// Gets the entry points declared by the `exports` of a `deno.json` or
+/// `deno.jsonc` file, which may be a string or a map of export names
+/// to paths relative to the config file.
+pub fn get_deno_json_entry_points(
+  config_url: &ModuleSpecifier,
+  text: &str,
+) -> Result<Vec<ModuleSpecifier>> {
+  let value = jsonc_parser::parse_to_serde_value(text, &Default::default())?
+    .unwrap_or_default();
+  let paths = match value.get("exports") {
+    Some(serde_json::Value::String(path)) => vec![path.as_str()],
+    Some(serde_json::Value::Object(exports)) => exports
+      .iter()
+      .map(|(name, path)| {
+        path.as_str().ok_or_else(|| {
+          anyhow::anyhow!(
+            "Expected the path of export '{}' in {} to be a string.",
+            name,
+            config_url
+          )
+        })
+      })
+      .collect::<Result<_>>()?,
+    Some(_) => anyhow::bail!(
+      "Expected the exports in {} to be a string or object.",
+      config_url
+    ),
+    None => Vec::new(),
+  };
+  let mut entry_points = paths
+    .into_iter()
+    .map(|path| Ok(config_url.join(path)?))
+    .collect::<Result<Vec<_>>>()?;
+  entry_points.sort();
+  entry_points.dedup();
+  Ok(entry_points)
+}
+
+/// Gets the deepest directory containing all the provided `file:` specifiers.
+/// Remote specifiers are ignored.
+///
+/// Returns `None` when there are no `file:` specifiers or they don't share
+/// a root (ex. different drives on Windows).
+pub fn common_ancestor(
+  specifiers: &[ModuleSpecifier],
+) -> Option<ModuleSpecifier> {
+  let mut file_specifiers = specifiers.iter().filter(|s| s.scheme() == "file");
+  let first = file_specifiers.next()?;
+  let mut common = get_dir_segments(first);
+  for specifier in file_specifiers {
+    if specifier.host_str() != first.host_str() {
+      return None;
+    }
+    let segments = get_dir_segments(specifier);
+    let common_len = common
+      .iter()
+      .zip(segments.iter())
+      .take_while(|(a, b)| a == b)
+      .count();
+    common.truncate(common_len);
+  }
+  let is_windows_path = first
+    .path_segments()
+    .and_then(|mut s| s.next())
+    .map(is_drive_letter)
+    .unwrap_or(false);
+  if is_windows_path && common.is_empty() {
+    return None;
+  }
+
+  let mut ancestor = first.clone();
+  ancestor.set_query(None);
+  ancestor.set_fragment(None);
+  ancestor.set_path(&format!("/{}", common.join("/")));
+  if !common.is_empty() {
+    ancestor.path_segments_mut().unwrap().push("");
+  }
+  Some(ancestor)
+}
+
+fn get_dir_segments(specifier: &ModuleSpecifier) -> Vec<&str> {
+  let mut segments = specifier
+    .path_segments()
+    .map(|s| s.collect::<Vec<_>>())
+    .unwrap_or_default();
+  // remove the file name
+  segments.pop();
+  segments
+}
+
+fn is_drive_letter(segment: &str) -> bool {
+  let bytes = segment.as_bytes();
+  bytes.len() == 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
 pub fn prepend_statement_to_text(
   file_path: &Path,
   file_text: &mut String,
@@ -215,8 +310,21 @@ pub fn text_change_for_prepend_statement_to_text(
 }
 
 fn top_file_insert_pos(program: Program) -> usize {
-  let mut pos = 0;
-  for comment in program.leading_comments() {
+  // insert after any shebang
+  let text = program.text_info().text_str();
+  let has_shebang = text.starts_with("#!");
+  let mut pos = if has_shebang {
+    text.find('\n').unwrap_or(text.len())
+  } else {
+    0
+  };
+  // the program starts at the shebang, so the comments after
+  // it are the leading comments of the first statement
+  let leading_comments = match program.children().first() {
+    Some(node) if has_shebang => node.leading_comments(),
+    _ => program.leading_comments(),
+  };
+  for comment in leading_comments {
     // insert before any @ts-ignore or @ts-expect
     if comment.text_fast(program).to_lowercase().contains("@ts-") {
       break;
@@ -244,6 +352,82 @@ pub fn with_extension(path: &Path, ext: &str) -> PathBuf {
   }
 }
 
+/// Formats JSON text with sorted object keys and two space indentation,
+/// keeping numbers as they're written so they don't lose precision.
+/// Returns `None` when the text isn't valid JSON.
+pub fn canonicalize_json(text: &str) -> Option<String> {
+  let value = jsonc_parser::parse_to_value(
+    strip_bom(text),
+    &jsonc_parser::ParseOptions {
+      allow_comments: false,
+      allow_loose_object_property_names: false,
+      allow_trailing_commas: false,
+    },
+  )
+  .ok()??;
+  let mut output = String::new();
+  write_canonical_json(&mut output, value, 0);
+  Some(output)
+}
+
+fn write_canonical_json(
+  output: &mut String,
+  value: jsonc_parser::JsonValue,
+  indent: usize,
+) {
+  use jsonc_parser::JsonValue;
+
+  let write_newline = |output: &mut String, indent: usize| {
+    output.push('\n');
+    output.push_str(&"  ".repeat(indent));
+  };
+  match value {
+    JsonValue::String(text) => {
+      output.push_str(&serde_json::to_string(text.as_ref()).unwrap())
+    }
+    JsonValue::Number(text) => output.push_str(text),
+    JsonValue::Boolean(value) => output.push_str(&value.to_string()),
+    JsonValue::Null => output.push_str("null"),
+    JsonValue::Array(array) => {
+      let items = array.take_inner();
+      if items.is_empty() {
+        output.push_str("[]");
+        return;
+      }
+      output.push('[');
+      for (i, item) in items.into_iter().enumerate() {
+        if i > 0 {
+          output.push(',');
+        }
+        write_newline(output, indent + 1);
+        write_canonical_json(output, item, indent + 1);
+      }
+      write_newline(output, indent);
+      output.push(']');
+    }
+    JsonValue::Object(object) => {
+      let mut entries = object.into_iter().collect::<Vec<_>>();
+      if entries.is_empty() {
+        output.push_str("{}");
+        return;
+      }
+      entries.sort_by(|a, b| a.0.cmp(&b.0));
+      output.push('{');
+      for (i, (key, value)) in entries.into_iter().enumerate() {
+        if i > 0 {
+          output.push(',');
+        }
+        write_newline(output, indent + 1);
+        output.push_str(&serde_json::to_string(&key).unwrap());
+        output.push_str(": ");
+        write_canonical_json(output, value, indent + 1);
+      }
+      write_newline(output, indent);
+      output.push('}');
+    }
+  }
+}
+
 #[cfg(test)]
 mod test {
   use std::collections::HashSet;
@@ -251,6 +435,42 @@ mod test {
   use super::*;
   use pretty_assertions::assert_eq;
 
+  #[test]
+  fn test_common_ancestor() {
+    fn run_test(specifiers: &[&str], expected: Option<&str>) {
+      let specifiers = specifiers
+        .iter()
+        .map(|s| ModuleSpecifier::parse(s).unwrap())
+        .collect::<Vec<_>>();
+      assert_eq!(
+        common_ancestor(&specifiers).as_ref().map(|s| s.as_str()),
+        expected
+      );
+    }
+
+    run_test(&[], None);
+    run_test(&["https://deno.land/x/mod.ts"], None);
+    run_test(&["file:///a/b/mod.ts"], Some("file:///a/b/"));
+    run_test(
+      &["file:///a/b/mod.ts", "file:///a/b/c/mod.ts"],
+      Some("file:///a/b/"),
+    );
+    run_test(
+      &[
+        "file:///a/b/c/mod.ts",
+        "https://deno.land/x/mod.ts",
+        "file:///a/d/mod.ts",
+      ],
+      Some("file:///a/"),
+    );
+    run_test(&["file:///a/mod.ts", "file:///b/mod.ts"], Some("file:///"));
+    run_test(
+      &["file:///C:/a/mod.ts", "file:///C:/b/mod.ts"],
+      Some("file:///C:/"),
+    );
+    run_test(&["file:///C:/a/mod.ts", "file:///D:/a/mod.ts"], None);
+  }
+
   #[test]
   fn test_path_with_stem_suffix() {
     assert_eq!(
@@ -431,4 +651,66 @@ mod test {
       PathBuf::from("/test/test")
     );
   }
+
+  #[test]
+  fn test_get_deno_json_entry_points() {
+    let config_url =
+      ModuleSpecifier::parse("file:///project/deno.json").unwrap();
+    let get = |text: &str| {
+      get_deno_json_entry_points(&config_url, text)
+        .map(|e| e.into_iter().map(|s| s.to_string()).collect::<Vec<_>>())
+    };
+    assert_eq!(
+      get(r#"{ "exports": "./mod.ts" }"#).unwrap(),
+      vec!["file:///project/mod.ts"]
+    );
+    assert_eq!(
+      get(
+        r#"{
+          // comment
+          "exports": {
+            ".": "./mod.ts",
+            "./utils": "./src/utils.ts",
+            "./mod": "./mod.ts",
+          }
+        }"#
+      )
+      .unwrap(),
+      vec!["file:///project/mod.ts", "file:///project/src/utils.ts"]
+    );
+    assert!(get(r#"{ "name": "@scope/pkg" }"#).unwrap().is_empty());
+    assert_eq!(
+      get(r#"{ "exports": { ".": 1 } }"#).err().unwrap().to_string(),
+      "Expected the path of export '.' in file:///project/deno.json to be a string."
+    );
+  }
+
+  #[test]
+  fn test_canonicalize_json() {
+    assert_eq!(
+      canonicalize_json(
+        r#"{"b":[3,1,{"d":null,"c":true}],"a":12345678901234567890.10,"e":{},"f":[],"\u00e9":"\"x\""}"#
+      )
+      .unwrap(),
+      concat!(
+        "{\n",
+        "  \"a\": 12345678901234567890.10,\n",
+        "  \"b\": [\n",
+        "    3,\n",
+        "    1,\n",
+        "    {\n",
+        "      \"c\": true,\n",
+        "      \"d\": null\n",
+        "    }\n",
+        "  ],\n",
+        "  \"e\": {},\n",
+        "  \"f\": [],\n",
+        "  \"é\": \"\\\"x\\\"\"\n",
+        "}",
+      )
+    );
+    assert_eq!(canonicalize_json("\u{FEFF}1").unwrap(), "1");
+    assert_eq!(canonicalize_json("{ // comment\n}"), None);
+    assert_eq!(canonicalize_json("{,}"), None);
+  }
 }
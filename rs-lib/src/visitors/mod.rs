@@ -3,9 +3,11 @@
 mod deno_comment_directives;
 mod globals;
 mod imports_exports;
+mod module_specifiers;
 mod polyfill;
 
 pub use deno_comment_directives::*;
 pub use globals::*;
 pub use imports_exports::*;
+pub use module_specifiers::*;
 pub use polyfill::*;
@@ -0,0 +1,63 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::view::*;
+use deno_ast::SourceRange;
+use deno_ast::SourceRanged;
+use deno_ast::SourceTextInfoProvider;
+use deno_ast::TextChange;
+
+/// Gets the text changes for replacing the module specifiers of any
+/// imports, exports, and dynamic imports using the provided mapper.
+pub fn get_module_specifier_text_changes(
+  program: Program,
+  mapper: &dyn Fn(&str) -> Option<String>,
+) -> Vec<TextChange> {
+  let mut text_changes = Vec::new();
+  visit_children(program.as_node(), program, mapper, &mut text_changes);
+  text_changes
+}
+
+fn visit_children(
+  node: Node,
+  program: Program,
+  mapper: &dyn Fn(&str) -> Option<String>,
+  text_changes: &mut Vec<TextChange>,
+) {
+  for child in node.children() {
+    let src = match child {
+      Node::ImportDecl(import_decl) => Some(import_decl.src),
+      Node::ExportAll(export_all) => Some(export_all.src),
+      Node::NamedExport(named_export) => named_export.src,
+      Node::TsImportType(ts_import_type) => Some(ts_import_type.arg),
+      Node::TsModuleDecl(module_decl) => match &module_decl.id {
+        TsModuleName::Str(src) => Some(*src),
+        TsModuleName::Ident(_) => None,
+      },
+      Node::CallExpr(call_expr)
+        if matches!(call_expr.callee, Callee::Import(_)) =>
+      {
+        match call_expr.args.first().map(|a| a.expr) {
+          Some(Expr::Lit(Lit::Str(src))) => Some(src),
+          _ => None,
+        }
+      }
+      _ => None,
+    };
+    match src {
+      Some(src) => {
+        if let Some(new_text) = mapper(src.value()) {
+          let range = SourceRange::new(src.start() + 1, src.end() - 1);
+          text_changes.push(TextChange {
+            range: range.as_byte_range(program.text_info().range().start),
+            new_text,
+          });
+        }
+        // dynamic imports may contain other dynamic imports in their arguments
+        if let Node::CallExpr(_) = child {
+          visit_children(child, program, mapper, text_changes);
+        }
+      }
+      None => visit_children(child, program, mapper, text_changes),
+    }
+  }
+}
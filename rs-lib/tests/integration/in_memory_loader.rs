@@ -1,9 +1,12 @@
 // Copyright 2018-2024 the Deno authors. MIT license.
 
+use std::cell::RefCell;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
 use std::pin::Pin;
+use std::rc::Rc;
 
 use anyhow::anyhow;
 use anyhow::Result;
@@ -22,15 +25,23 @@ type RemoteFileResult = Result<(RemoteFileText, RemoteFileHeaders), String>;
 
 #[derive(Clone)]
 pub struct InMemoryLoader {
-  local_files: HashMap<PathBuf, String>,
+  local_files: HashMap<PathBuf, Vec<u8>>,
+  local_file_errors: HashMap<PathBuf, String>,
   remote_files: HashMap<ModuleSpecifier, RemoteFileResult>,
+  cache_settings: Rc<RefCell<HashMap<ModuleSpecifier, CacheSetting>>>,
+  load_counts: Rc<RefCell<HashMap<ModuleSpecifier, usize>>>,
+  remote_yields: usize,
 }
 
 impl InMemoryLoader {
   pub fn new() -> Self {
     Self {
       local_files: HashMap::new(),
+      local_file_errors: HashMap::new(),
       remote_files: HashMap::new(),
+      cache_settings: Default::default(),
+      load_counts: Default::default(),
+      remote_yields: 0,
     }
   }
 
@@ -38,10 +49,29 @@ impl InMemoryLoader {
     &mut self,
     path: impl AsRef<Path>,
     text: impl AsRef<str>,
+  ) -> &mut Self {
+    self.add_local_file_bytes(path, text.as_ref().as_bytes())
+  }
+
+  pub fn add_local_file_bytes(
+    &mut self,
+    path: impl AsRef<Path>,
+    bytes: &[u8],
   ) -> &mut Self {
     self
       .local_files
-      .insert(path.as_ref().to_path_buf(), text.as_ref().to_string());
+      .insert(path.as_ref().to_path_buf(), bytes.to_vec());
+    self
+  }
+
+  pub fn add_local_file_with_error(
+    &mut self,
+    path: impl AsRef<Path>,
+    error_text: impl AsRef<str>,
+  ) -> &mut Self {
+    self
+      .local_file_errors
+      .insert(path.as_ref().to_path_buf(), error_text.as_ref().to_string());
     self
   }
 
@@ -85,23 +115,76 @@ impl InMemoryLoader {
     );
     self
   }
+
+  /// Makes remote loads yield to the executor this many times before
+  /// completing, so that concurrent loads overlap.
+  pub fn set_remote_yields(&mut self, count: usize) -> &mut Self {
+    self.remote_yields = count;
+    self
+  }
+
+  /// Gets the cache setting the specifier was last loaded with.
+  pub fn cache_setting(&self, specifier: &str) -> Option<CacheSetting> {
+    self
+      .cache_settings
+      .borrow()
+      .get(&ModuleSpecifier::parse(specifier).unwrap())
+      .copied()
+  }
+
+  /// Gets the number of times the specifier was loaded.
+  pub fn load_count(&self, specifier: &str) -> usize {
+    self
+      .load_counts
+      .borrow()
+      .get(&ModuleSpecifier::parse(specifier).unwrap())
+      .copied()
+      .unwrap_or(0)
+  }
 }
 
 impl Loader for InMemoryLoader {
+  fn list_dir(&self, dir: &ModuleSpecifier) -> Option<Vec<String>> {
+    let dir = url_to_file_path(dir).ok()?;
+    let names = self
+      .local_files
+      .keys()
+      .filter_map(|path| {
+        let relative = path.strip_prefix(&dir).ok()?;
+        let name = relative.components().next()?;
+        Some(name.as_os_str().to_string_lossy().to_string())
+      })
+      .collect::<BTreeSet<_>>();
+    Some(names.into_iter().collect())
+  }
+
   fn load(
     &self,
     specifier: ModuleSpecifier,
-    _cache_setting: CacheSetting,
+    cache_setting: CacheSetting,
     _maybe_checksum: Option<LoaderChecksum>,
   ) -> Pin<Box<dyn Future<Output = Result<Option<LoadResponse>>> + 'static>> {
+    self
+      .cache_settings
+      .borrow_mut()
+      .insert(specifier.clone(), cache_setting);
+    *self
+      .load_counts
+      .borrow_mut()
+      .entry(specifier.clone())
+      .or_default() += 1;
     if specifier.scheme() == "file" {
       let file_path = url_to_file_path(&specifier).unwrap();
+      if let Some(err) = self.local_file_errors.get(&file_path) {
+        return Box::pin(futures::future::ready(Err(anyhow!("{}", err))));
+      }
       let result = self.local_files.get(&file_path).map(ToOwned::to_owned);
       return Box::pin(async move {
         Ok(result.map(|result| LoadResponse {
-          content: result.into_bytes(),
+          content: result,
           headers: None,
           specifier,
+          from_cache: false,
         }))
       });
     }
@@ -113,6 +196,7 @@ impl Loader for InMemoryLoader {
           specifier, // todo: test a re-direct
           content: result.0.clone().into(),
           headers: result.1.clone(),
+          from_cache: false,
         }),
         Err(err) => Err(err),
       });
@@ -121,6 +205,12 @@ impl Loader for InMemoryLoader {
       Some(Err(err)) => Err(anyhow!("{}", err)),
       None => Ok(None),
     };
-    Box::pin(futures::future::ready(result))
+    let remote_yields = self.remote_yields;
+    Box::pin(async move {
+      for _ in 0..remote_yields {
+        tokio::task::yield_now().await;
+      }
+      result
+    })
   }
 }
@@ -7,6 +7,7 @@ use anyhow::Result;
 use deno_node_transform::transform;
 use deno_node_transform::GlobalName;
 use deno_node_transform::MappedSpecifier;
+use deno_node_transform::ModuleGraphOptions;
 use deno_node_transform::ModuleSpecifier;
 use deno_node_transform::PackageMappedSpecifier;
 use deno_node_transform::PackageShim;
@@ -27,6 +28,7 @@ pub struct TestBuilder {
   test_shims: Vec<Shim>,
   target: ScriptTarget,
   import_map: Option<ModuleSpecifier>,
+  jsr_url: Option<ModuleSpecifier>,
 }
 
 impl TestBuilder {
@@ -42,6 +44,7 @@ impl TestBuilder {
       test_shims: Default::default(),
       target: ScriptTarget::ES5,
       import_map: None,
+      jsr_url: None,
     }
   }
 
@@ -53,6 +56,10 @@ impl TestBuilder {
     self
   }
 
+  pub fn loader(&self) -> &InMemoryLoader {
+    &self.loader
+  }
+
   pub fn entry_point(&mut self, value: impl AsRef<str>) -> &mut Self {
     self.entry_point = value.as_ref().to_string();
     self
@@ -75,6 +82,11 @@ impl TestBuilder {
     self
   }
 
+  pub fn set_jsr_url(&mut self, url: impl AsRef<str>) -> &mut Self {
+    self.jsr_url = Some(ModuleSpecifier::parse(url.as_ref()).unwrap());
+    self
+  }
+
   pub fn add_default_shims(&mut self) -> &mut Self {
     let deno_shim = Shim::Package(PackageShim {
       package: PackageMappedSpecifier {
@@ -164,29 +176,50 @@ impl TestBuilder {
     self
   }
 
+  /// Options for building a module graph directly from this builder.
+  pub fn graph_options(&self) -> ModuleGraphOptions<'_> {
+    ModuleGraphOptions {
+      entry_points: self.entry_points(),
+      test_entry_points: self.test_entry_points(),
+      loader: Some(Rc::new(self.loader.clone())),
+      specifier_mappings: &self.specifier_mappings,
+      import_map: self.import_map.clone(),
+      ..Default::default()
+    }
+  }
+
   pub async fn transform(&self) -> Result<TransformOutput> {
-    let mut entry_points =
-      vec![ModuleSpecifier::parse(&self.entry_point).unwrap()];
-    entry_points.extend(
-      self
-        .additional_entry_points
-        .iter()
-        .map(|p| ModuleSpecifier::parse(p).unwrap()),
-    );
     transform(TransformOptions {
-      entry_points,
-      test_entry_points: self
-        .test_entry_points
-        .iter()
-        .map(|p| ModuleSpecifier::parse(p).unwrap())
-        .collect(),
+      entry_points: self.entry_points(),
+      test_entry_points: self.test_entry_points(),
       shims: self.shims.clone(),
       test_shims: self.test_shims.clone(),
       loader: Some(Rc::new(self.loader.clone())),
       specifier_mappings: self.specifier_mappings.clone(),
       target: self.target,
       import_map: self.import_map.clone(),
+      jsr_url: self.jsr_url.clone(),
     })
     .await
   }
+
+  fn entry_points(&self) -> Vec<ModuleSpecifier> {
+    let mut entry_points =
+      vec![ModuleSpecifier::parse(&self.entry_point).unwrap()];
+    entry_points.extend(
+      self
+        .additional_entry_points
+        .iter()
+        .map(|p| ModuleSpecifier::parse(p).unwrap()),
+    );
+    entry_points
+  }
+
+  fn test_entry_points(&self) -> Vec<ModuleSpecifier> {
+    self
+      .test_entry_points
+      .iter()
+      .map(|p| ModuleSpecifier::parse(p).unwrap())
+      .collect()
+  }
 }
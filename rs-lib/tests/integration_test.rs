@@ -1,15 +1,48 @@
 // Copyright 2018-2024 the Deno authors. MIT license.
 
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+use deno_node_transform::default_module_extensions;
+use deno_node_transform::AbortHandle;
+use deno_node_transform::CacheSetting;
+use deno_node_transform::CacheStats;
+use deno_node_transform::Collision;
+use deno_node_transform::Cycle;
+use deno_node_transform::CycleKind;
 use deno_node_transform::Dependency;
+use deno_node_transform::EncodingError;
 use deno_node_transform::GlobalName;
+use deno_node_transform::GraphBuildError;
+use deno_node_transform::HostPattern;
+use deno_node_transform::JsrUrlProvider;
+use deno_node_transform::MappedSpecifier;
+use deno_node_transform::MediaType;
+use deno_node_transform::ModuleGraph;
+use deno_node_transform::ModuleGraphOptions;
+use deno_node_transform::ModuleGraphView;
+use deno_node_transform::ModuleLoadErrorKind;
 use deno_node_transform::ModuleShim;
+use deno_node_transform::ModuleSpecifier;
 use deno_node_transform::PackageMappedSpecifier;
 use deno_node_transform::PackageShim;
+use deno_node_transform::PackageVersionSource;
+use deno_node_transform::Reporter;
+use deno_node_transform::Resolver;
 use deno_node_transform::ScriptTarget;
 use deno_node_transform::Shim;
+use deno_node_transform::SlowTypeKind;
+use deno_node_transform::Specifiers;
+use deno_node_transform::SyntaxOptions;
+use deno_node_transform::TsFeatureSet;
+use deno_node_transform::WasmExport;
+use deno_node_transform::WasmExternalKind;
+use deno_node_transform::WasmImport;
+use deno_node_transform::WasmModuleInfo;
 use pretty_assertions::assert_eq;
+use regex::Regex;
 
 #[macro_use]
 mod integration;
@@ -367,6 +400,22 @@ async fn no_transform_deno_ignored() {
     .await;
 }
 
+#[tokio::test]
+async fn transform_unanalyzable_dynamic_import_warnings() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file("/mod.ts", "const name = './a.js';\nimport(name);");
+    })
+    .transform()
+    .await
+    .unwrap();
+
+  assert_eq!(
+    result.warnings,
+    vec!["Dynamic import with a non-literal specifier will not be rewritten.\n    at file:///mod.ts:2:1"]
+  );
+}
+
 #[tokio::test]
 async fn transform_legacy_deno_shim_ignore_warnings() {
   // this was renamed to dnt-shim-ignore
@@ -2079,6 +2128,3738 @@ async fn node_specifier() {
   );
 }
 
+#[tokio::test]
+async fn graph_max_total_bytes() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import './a.ts'; import './b.ts';")
+      .add_local_file("/a.ts", "export const a = 'aaaaaaaaaa';")
+      .add_local_file("/b.ts", "export const b = 'bbbbbbbbbb';");
+  });
+
+  let err_message = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    max_total_bytes: Some(50),
+    ..test_builder.graph_options()
+  })
+  .await
+  .err()
+  .unwrap()
+  .to_string();
+  assert!(
+    err_message.contains("download budget exceeded"),
+    "{}",
+    err_message
+  );
+
+  let (_, specifiers) =
+    ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+      max_total_bytes: Some(1024),
+      ..test_builder.graph_options()
+    })
+    .await
+    .unwrap();
+  assert_eq!(specifiers.local.len(), 3);
+}
+
+#[tokio::test]
+async fn graph_meta_resolve_targets() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader.add_local_file(
+      "/sub/mod.ts",
+      concat!(
+        "const a = import.meta.resolve('./asset.bin');\n",
+        "const b = import.meta.resolve(`../other.txt`);\n",
+        "const c = import.meta.resolve(a);\n",
+        "const d = import.meta.resolve('https://localhost/data.json');\n",
+      ),
+    );
+  });
+  test_builder.entry_point("file:///sub/mod.ts");
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let targets = graph
+    .meta_resolve_targets(
+      &ModuleSpecifier::parse("file:///sub/mod.ts").unwrap(),
+    )
+    .into_iter()
+    .map(|s| s.to_string())
+    .collect::<Vec<_>>();
+  assert_eq!(
+    targets,
+    vec![
+      "file:///sub/asset.bin",
+      "file:///other.txt",
+      "https://localhost/data.json",
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_type_only_modules() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "import type { A } from './types.ts';\n",
+          "import { b } from './b.ts';\n",
+          "export type { C } from './c.ts';\n",
+        ),
+      )
+      .add_local_file(
+        "/types.ts",
+        "import './types_dep.ts'; export type A = string;",
+      )
+      .add_local_file("/types_dep.ts", "export {};")
+      .add_local_file(
+        "/b.ts",
+        "import type { A } from './types.ts'; export const b = 5;",
+      )
+      .add_local_file("/c.ts", "export type C = string;");
+  });
+
+  let (_, specifiers) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let mut type_only = specifiers
+    .type_only_modules()
+    .iter()
+    .map(|s| s.to_string())
+    .collect::<Vec<_>>();
+  type_only.sort();
+  assert_eq!(
+    type_only,
+    vec!["file:///c.ts", "file:///types.ts", "file:///types_dep.ts"]
+  );
+  // still resolved and parsed
+  assert_eq!(specifiers.local.len(), 5);
+}
+
+#[tokio::test]
+async fn graph_integrity() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import 'https://localhost/mod.ts';")
+      .add_remote_file("https://localhost/mod.ts", "foo");
+  });
+  let remote_specifier =
+    ModuleSpecifier::parse("https://localhost/mod.ts").unwrap();
+
+  for value in [
+    "sha256-LCa0a2j/xo/5m0U8HTBBNBNCLXBkg7+g+YpeiGJm564=",
+    "sha512-9/u6bgY2+JDlb7vzKD5STG+jIErimDgtYkdB0NxmODJuKCxBvl5CVNiCB3LFUYosWowMf37aGVlKfrU5RT4e1w==",
+  ] {
+    let result = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+      integrity: [(remote_specifier.clone(), value.to_string())]
+        .into_iter()
+        .collect(),
+      ..test_builder.graph_options()
+    })
+    .await;
+    assert!(result.is_ok(), "{}", value);
+  }
+
+  let err_message = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    integrity: [(
+      remote_specifier.clone(),
+      "sha384-AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"
+        .to_string(),
+    )]
+    .into_iter()
+    .collect(),
+    ..test_builder.graph_options()
+  })
+  .await
+  .err()
+  .unwrap()
+  .to_string();
+  assert!(
+    err_message.contains("Failed integrity check for https://localhost/mod.ts"),
+    "{}",
+    err_message
+  );
+
+  let err_message = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    integrity: [(remote_specifier, "md5-abc".to_string())]
+      .into_iter()
+      .collect(),
+    ..test_builder.graph_options()
+  })
+  .await
+  .err()
+  .unwrap();
+  assert_eq!(
+    format!("{:#}", err_message),
+    "Invalid integrity for https://localhost/mod.ts: Unsupported integrity algorithm 'md5' in 'md5-abc'. Expected sha256, sha384, or sha512."
+  );
+}
+
+#[cfg(feature = "serialization")]
+#[tokio::test]
+async fn graph_to_json_round_trip() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "import * as fs from 'node:fs';\n",
+          "import data from './data.json' with { type: 'json' };\n",
+          "import type { A } from './types.d.ts';\n",
+          "export * from 'https://localhost/mod.js';\n",
+          "const other = await import('./other.ts');\n",
+        ),
+      )
+      .add_local_file("/data.json", r#"{ "a": 1 }"#)
+      .add_local_file("/types.d.ts", "export type A = string;")
+      .add_local_file("/other.ts", "export const other = 1;")
+      .add_remote_file_with_headers(
+        "https://localhost/mod.js",
+        "export const value = 5;",
+        &[
+          ("content-type", "application/javascript"),
+          ("x-typescript-types", "./mod.d.ts"),
+        ],
+      )
+      .add_remote_file(
+        "https://localhost/mod.d.ts",
+        "export declare const value: number;",
+      );
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let json = graph.to_json();
+  assert_eq!(json["roots"], serde_json::json!(["file:///mod.ts"]));
+  assert_eq!(json["externals"], serde_json::json!(["node:fs"]));
+  let mod_json = json["modules"]
+    .as_array()
+    .unwrap()
+    .iter()
+    .find(|m| m["specifier"] == "file:///mod.ts")
+    .unwrap();
+  assert_eq!(mod_json["kind"], "js");
+  assert_eq!(mod_json["mediaType"], "TypeScript");
+  assert_eq!(
+    mod_json["dependencies"],
+    serde_json::json!([
+      { "specifier": "node:fs", "code": "node:fs" },
+      { "specifier": "./data.json", "code": "file:///data.json" },
+      { "specifier": "./types.d.ts", "type": "file:///types.d.ts" },
+      { "specifier": "https://localhost/mod.js", "code": "https://localhost/mod.js" },
+      { "specifier": "./other.ts", "code": "file:///other.ts", "isDynamic": true },
+    ])
+  );
+
+  let round_tripped = ModuleGraph::from_json(json.clone()).await.unwrap();
+  assert_eq!(round_tripped.to_json(), json);
+  assert_eq!(round_tripped.all_modules().count(), 7);
+}
+
+#[cfg(feature = "serialization")]
+#[tokio::test]
+async fn graph_from_json_redirect_cycle() {
+  let err = ModuleGraph::from_json(serde_json::json!({
+    "roots": ["https://localhost/a.ts"],
+    "modules": [],
+    "redirects": {
+      "https://localhost/a.ts": "https://localhost/b.ts",
+      "https://localhost/b.ts": "https://localhost/a.ts",
+    },
+    "externals": [],
+  }))
+  .await
+  .err()
+  .unwrap();
+  assert_eq!(
+    err.to_string(),
+    "The redirects of https://localhost/a.ts form a cycle."
+  );
+}
+
+#[tokio::test]
+async fn graph_fetched_assets() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader.add_local_file(
+      "/sub/mod.ts",
+      concat!(
+        "await fetch(new URL('./data.json', import.meta.url));\n",
+        "await fetch(new URL(\"../img.png\", import.meta.url), {});\n",
+        "await fetch(new URL(path, import.meta.url));\n",
+        "await fetch(new URL('./other.json', base));\n",
+        "await fetch('./relative.json');\n",
+        "function test(fetch) { fetch(new URL('./shadowed.json', import.meta.url)); }\n",
+      ),
+    );
+  });
+  test_builder.entry_point("file:///sub/mod.ts");
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let assets = graph
+    .fetched_assets(&ModuleSpecifier::parse("file:///sub/mod.ts").unwrap())
+    .into_iter()
+    .map(|s| s.to_string())
+    .collect::<Vec<_>>();
+  assert_eq!(assets, vec!["file:///sub/data.json", "file:///img.png"]);
+}
+
+#[tokio::test]
+async fn graph_default_media_type() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import 'https://localhost/mod.xyz';")
+      .add_remote_file("https://localhost/mod.xyz", "export const a = 5;");
+  });
+
+  // otherwise the unknown extension is loaded as an asset
+  let err_message = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    module_extensions: Default::default(),
+    ..test_builder.graph_options()
+  })
+  .await
+  .err()
+  .unwrap()
+  .to_string();
+  assert!(
+    err_message.contains("Expected a JavaScript or TypeScript module"),
+    "{}",
+    err_message
+  );
+
+  let specifier = ModuleSpecifier::parse("https://localhost/mod.xyz").unwrap();
+  for module_extensions in [Default::default(), default_module_extensions()] {
+    let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+      default_media_type: Some(MediaType::JavaScript),
+      module_extensions,
+      ..test_builder.graph_options()
+    })
+    .await
+    .unwrap();
+    assert_eq!(
+      graph.get(&specifier).js().unwrap().media_type,
+      MediaType::JavaScript
+    );
+    assert_eq!(
+      graph.warnings(),
+      &["Could not determine the media type of https://localhost/mod.xyz, so it was treated as JavaScript.".to_string()]
+    );
+  }
+}
+
+#[tokio::test]
+async fn graph_default_media_type_default_module_extensions() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import 'https://localhost/mod.xyz';")
+      .add_remote_file("https://localhost/mod.xyz", "export const a = 5;");
+  });
+  let specifier = ModuleSpecifier::parse("https://localhost/mod.xyz").unwrap();
+
+  // unknown extensions are assets when there's no default media type
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  assert!(graph.assets().contains_key(&specifier));
+
+  // otherwise the default media type takes precedence
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    default_media_type: Some(MediaType::JavaScript),
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  assert_eq!(
+    graph.get(&specifier).js().unwrap().media_type,
+    MediaType::JavaScript
+  );
+  assert!(!graph.assets().contains_key(&specifier));
+}
+
+#[tokio::test]
+async fn graph_cache_policy() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "import 'https://deno.land/std/mod.ts';\n",
+          "import 'https://cdn.example.com/mod.ts';\n",
+          "import 'https://localhost/mod.ts';\n",
+        ),
+      )
+      .add_remote_file("https://deno.land/std/mod.ts", "export {};")
+      .add_remote_file("https://cdn.example.com/mod.ts", "export {};")
+      .add_remote_file("https://localhost/mod.ts", "export {};");
+  });
+
+  ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    cache_policy: HashMap::from([
+      (HostPattern::new("deno.land/std"), CacheSetting::Only),
+      (HostPattern::new("*.example.com"), CacheSetting::Reload),
+    ]),
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  let loader = test_builder.loader();
+  assert_eq!(
+    loader.cache_setting("https://deno.land/std/mod.ts"),
+    Some(CacheSetting::Only)
+  );
+  assert_eq!(
+    loader.cache_setting("https://cdn.example.com/mod.ts"),
+    Some(CacheSetting::Reload)
+  );
+  assert_eq!(
+    loader.cache_setting("https://localhost/mod.ts"),
+    Some(CacheSetting::Use)
+  );
+}
+
+#[tokio::test]
+async fn graph_cache_setting() {
+  let mut test_builder = TestBuilder::new();
+  test_builder
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          concat!(
+            "import 'https://localhost/mod.ts';\n",
+            "import 'https://deno.land/std/mod.ts';\n",
+          ),
+        )
+        .add_remote_file("https://localhost/import_map.json", "{}")
+        .add_remote_file("https://localhost/mod.ts", "export {};")
+        .add_remote_file("https://deno.land/std/mod.ts", "export {};");
+    })
+    .set_import_map("https://localhost/import_map.json");
+
+  ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    cache_setting: CacheSetting::Reload,
+    cache_policy: HashMap::from([(
+      HostPattern::new("deno.land/std"),
+      CacheSetting::Use,
+    )]),
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  let loader = test_builder.loader();
+  assert_eq!(
+    loader.cache_setting("https://localhost/import_map.json"),
+    Some(CacheSetting::Reload)
+  );
+  assert_eq!(
+    loader.cache_setting("https://localhost/mod.ts"),
+    Some(CacheSetting::Reload)
+  );
+  assert_eq!(
+    loader.cache_setting("https://deno.land/std/mod.ts"),
+    Some(CacheSetting::Use)
+  );
+  assert_eq!(
+    loader.cache_setting("file:///mod.ts"),
+    Some(CacheSetting::Use)
+  );
+}
+
+#[tokio::test]
+async fn graph_deno_global_usages() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader.add_local_file(
+      "/mod.ts",
+      concat!(
+        "const text = await Deno.readTextFile('a.txt');\n",
+        "const { env } = Deno;\n",
+        "globalThis.Deno[\"exit\"](1);\n",
+        "function test(Deno: any) { Deno.writeTextFile('b.txt', ''); }\n",
+        "type T = typeof Deno.noColor;\n",
+      ),
+    );
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let usages = graph
+    .deno_global_usages(&ModuleSpecifier::parse("file:///mod.ts").unwrap())
+    .into_iter()
+    .map(|usage| {
+      (
+        usage.name,
+        usage.range.start.line,
+        usage.range.start.character,
+      )
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(
+    usages,
+    vec![
+      ("Deno.readTextFile".to_string(), 0, 19),
+      ("Deno.env".to_string(), 1, 8),
+      ("Deno.exit".to_string(), 2, 0),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_global_usages() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader.add_local_file(
+      "/mod.ts",
+      concat!(
+        "const res: Response = new Response();\n",
+        "crypto.subtle.digest('SHA-256', new Uint8Array());\n",
+        "console.log(globalThis.location, { performance: 1 });\n",
+        "function test(crypto: any) { return crypto; }\n",
+      ),
+    );
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let usages = graph.global_usages(
+    &ModuleSpecifier::parse("file:///mod.ts").unwrap(),
+    &["Response", "crypto", "location", "performance"],
+  );
+  let mut usages = usages
+    .into_iter()
+    .map(|(name, ranges)| {
+      let positions = ranges
+        .into_iter()
+        .map(|r| (r.start.line, r.start.character))
+        .collect::<Vec<_>>();
+      (name, positions)
+    })
+    .collect::<Vec<_>>();
+  usages.sort();
+  assert_eq!(
+    usages,
+    vec![
+      ("Response".to_string(), vec![(0, 26)]),
+      ("crypto".to_string(), vec![(1, 0)]),
+      ("location".to_string(), vec![(2, 12)]),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_rewrite_imports() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "import { a } from './a.ts';\n",
+          "export * from './b.ts';\n",
+          "export { c } from \"./c.ts\";\n",
+          "const d = await import('./d.ts');\n",
+          "type E = import('./e.ts').E;\n",
+        ),
+      )
+      .add_local_file("/a.ts", "export const a = 1;")
+      .add_local_file("/b.ts", "export const b = 1;")
+      .add_local_file("/c.ts", "export const c = 1;")
+      .add_local_file("/d.ts", "export const d = 1;")
+      .add_local_file("/e.ts", "export type E = 1;")
+      .add_local_file("/data.json", "{}");
+  });
+  test_builder.add_entry_point("file:///data.json");
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let text = graph.rewrite_imports(
+    &ModuleSpecifier::parse("file:///mod.ts").unwrap(),
+    |specifier| {
+      if specifier == "./b.ts" {
+        None
+      } else {
+        Some(specifier.replace(".ts", ".js"))
+      }
+    },
+  );
+  assert_eq!(
+    text.unwrap(),
+    concat!(
+      "import { a } from './a.js';\n",
+      "export * from './b.ts';\n",
+      "export { c } from \"./c.js\";\n",
+      "const d = await import('./d.js');\n",
+      "type E = import('./e.js').E;\n",
+    )
+  );
+  // modules without a parsed source aren't rewritten
+  assert_eq!(
+    graph.rewrite_imports(
+      &ModuleSpecifier::parse("file:///data.json").unwrap(),
+      |specifier| Some(specifier.to_string()),
+    ),
+    None
+  );
+}
+
+#[tokio::test]
+async fn graph_case_insensitive_file_specifiers() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        "import './foo.ts';\nimport './a.ts';\nimport './sub/bar.ts';",
+      )
+      .add_local_file("/a.ts", "import './Foo.ts';")
+      .add_local_file("/Foo.ts", "export {};")
+      .add_local_file("/Sub/Bar.ts", "export {};");
+  });
+
+  let err_message =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .err()
+      .unwrap()
+      .to_string();
+  assert!(
+    err_message.contains("Module not found \"file:///foo.ts\""),
+    "{}",
+    err_message
+  );
+
+  let (graph, specifiers) =
+    ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+      case_insensitive_file_specifiers: true,
+      ..test_builder.graph_options()
+    })
+    .await
+    .unwrap();
+  let mut local = specifiers
+    .local
+    .iter()
+    .map(|s| s.to_string())
+    .collect::<Vec<_>>();
+  local.sort();
+  assert_eq!(
+    local,
+    vec![
+      "file:///Foo.ts",
+      "file:///Sub/Bar.ts",
+      "file:///a.ts",
+      "file:///mod.ts"
+    ]
+  );
+  assert_eq!(
+    graph.resolve(&ModuleSpecifier::parse("file:///foo.ts").unwrap()),
+    ModuleSpecifier::parse("file:///Foo.ts").unwrap()
+  );
+}
+
+#[tokio::test]
+async fn graph_known_redirects() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import 'https://localhost/mod.ts';")
+      .add_remote_file("https://localhost/mod@1.0.0.ts", "export {};");
+  });
+
+  let old = ModuleSpecifier::parse("https://localhost/mod.ts").unwrap();
+  let new = ModuleSpecifier::parse("https://localhost/mod@1.0.0.ts").unwrap();
+  let (graph, specifiers) =
+    ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+      known_redirects: BTreeMap::from([(old.clone(), new.clone())]),
+      ..test_builder.graph_options()
+    })
+    .await
+    .unwrap();
+  assert_eq!(graph.redirects(), &BTreeMap::from([(old, new.clone())]));
+  assert_eq!(specifiers.remote, vec![new]);
+  let loader = test_builder.loader();
+  assert_eq!(loader.cache_setting("https://localhost/mod.ts"), None);
+}
+
+#[tokio::test]
+async fn graph_unanalyzable_dynamic_imports() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "await import('./a.ts');\n",
+          "await import(`./a.ts`);\n",
+          "const name = 'a';\n",
+          "await import(`./${name}.ts`);\n",
+          "await import(name);\n",
+        ),
+      )
+      .add_local_file("/a.ts", "export {};");
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let ranges = graph
+    .unanalyzable_dynamic_imports(
+      &ModuleSpecifier::parse("file:///mod.ts").unwrap(),
+    )
+    .into_iter()
+    .map(|r| {
+      (
+        (r.start.line, r.start.character),
+        (r.end.line, r.end.character),
+      )
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(ranges, vec![((3, 6), (3, 28)), ((4, 6), (4, 18))]);
+}
+
+#[tokio::test]
+async fn graph_strip_query_and_fragment() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import './a.ts?v=1';\nimport './b.ts#b';")
+      .add_local_file("/a.ts", "export {};")
+      .add_local_file("/b.ts", "export {};");
+  });
+  let referrer = ModuleSpecifier::parse("file:///mod.ts").unwrap();
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  assert_eq!(
+    graph
+      .resolve_dependency("./a.ts?v=1", &referrer)
+      .unwrap()
+      .as_str(),
+    "file:///a.ts?v=1"
+  );
+  assert_eq!(
+    graph
+      .resolve_dependency("./b.ts#b", &referrer)
+      .unwrap()
+      .as_str(),
+    "file:///b.ts#b"
+  );
+
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    strip_query_and_fragment: true,
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  assert_eq!(
+    graph
+      .resolve_dependency("./a.ts?v=1", &referrer)
+      .unwrap()
+      .as_str(),
+    "file:///a.ts"
+  );
+  assert_eq!(
+    graph
+      .resolve_dependency("./b.ts#b", &referrer)
+      .unwrap()
+      .as_str(),
+    "file:///b.ts"
+  );
+}
+
+#[tokio::test]
+async fn graph_resolve_fallback() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader.add_local_file("/mod.ts", "export {};");
+  });
+  let referrer = ModuleSpecifier::parse("file:///mod.ts").unwrap();
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  assert_eq!(graph.resolve_dependency("registry:a", &referrer), None);
+
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    resolve_fallback: Some(Box::new(|value, _referrer| {
+      let name = value.strip_prefix("registry:")?;
+      ModuleSpecifier::parse(&format!("https://localhost/{}.ts", name)).ok()
+    })),
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  assert_eq!(
+    graph
+      .resolve_dependency("registry:a", &referrer)
+      .unwrap()
+      .as_str(),
+    "https://localhost/a.ts"
+  );
+  assert_eq!(graph.resolve_dependency("other:a", &referrer), None);
+  assert_eq!(
+    graph.resolve_dependency("./mod.ts", &referrer),
+    Some(referrer.clone())
+  );
+}
+
+#[tokio::test]
+async fn graph_production_and_test_views() {
+  let mut test_builder = TestBuilder::new();
+  test_builder
+    .with_loader(|loader| {
+      loader
+        .add_local_file("/mod.ts", "import './a.ts';")
+        .add_local_file("/mod.test.ts", "import './mod.ts';\nimport './b.ts';")
+        .add_local_file("/a.ts", "export {};")
+        .add_local_file("/b.ts", "export {};");
+    })
+    .add_test_entry_point("file:///mod.test.ts");
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let get_specifiers = |view: ModuleGraphView| {
+    let mut specifiers = view
+      .modules()
+      .map(|m| m.specifier().to_string())
+      .collect::<Vec<_>>();
+    specifiers.sort();
+    specifiers
+  };
+  assert_eq!(
+    get_specifiers(graph.production_view()),
+    vec!["file:///a.ts", "file:///mod.ts"]
+  );
+  assert_eq!(
+    get_specifiers(graph.test_view()),
+    vec![
+      "file:///a.ts",
+      "file:///b.ts",
+      "file:///mod.test.ts",
+      "file:///mod.ts"
+    ]
+  );
+  let b = ModuleSpecifier::parse("file:///b.ts").unwrap();
+  assert!(!graph.production_view().contains(&b));
+  assert!(graph.production_view().get(&b).is_none());
+  assert!(graph.test_view().get(&b).is_some());
+}
+
+#[tokio::test]
+async fn graph_module_extensions() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        "import './a.js';\nimport './styles.css';\nimport './data.json' with { type: 'json' };",
+      )
+      .add_local_file("/a.js", "export {};")
+      .add_local_file("/styles.css", "body { color: red; }")
+      .add_local_file("/data.json", "{}");
+  });
+
+  let (graph, specifiers) =
+    ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+      module_extensions: default_module_extensions(),
+      ..test_builder.graph_options()
+    })
+    .await
+    .unwrap();
+  let mut local = specifiers
+    .local
+    .iter()
+    .map(|s| s.to_string())
+    .collect::<Vec<_>>();
+  local.sort();
+  assert_eq!(
+    local,
+    vec!["file:///a.js", "file:///data.json", "file:///mod.ts"]
+  );
+  let styles = ModuleSpecifier::parse("file:///styles.css").unwrap();
+  assert_eq!(
+    graph.assets(),
+    &BTreeMap::from([(styles, b"body { color: red; }".to_vec())])
+  );
+}
+
+#[tokio::test]
+async fn graph_module_extensions_versioned_url() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        "import 'https://localhost/preact@10.19.0';\nimport './styles.css';",
+      )
+      .add_local_file("/styles.css", "body { color: red; }")
+      .add_remote_file_with_headers(
+        "https://localhost/preact@10.19.0",
+        "export const h = 1;",
+        &[("content-type", "application/javascript")],
+      );
+  });
+
+  let (graph, specifiers) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  assert_eq!(
+    specifiers
+      .remote
+      .iter()
+      .map(|s| s.as_str())
+      .collect::<Vec<_>>(),
+    vec!["https://localhost/preact@10.19.0"]
+  );
+  assert_eq!(
+    graph
+      .assets()
+      .keys()
+      .map(|s| s.as_str())
+      .collect::<Vec<_>>(),
+    vec!["file:///styles.css"]
+  );
+}
+
+#[tokio::test]
+async fn graph_require_calls() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader.add_local_file(
+      "/mod.js",
+      concat!(
+        "const fs = require('fs');\n",
+        "const name = 'path';\n",
+        "const path = require(name);\n",
+        "function test(require) { require('other'); }\n",
+      ),
+    );
+  });
+  test_builder.entry_point("file:///mod.js");
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let specifier = ModuleSpecifier::parse("file:///mod.js").unwrap();
+  let calls = graph
+    .require_calls(&specifier)
+    .into_iter()
+    .map(|(value, range)| (value, range.start.line, range.start.character))
+    .collect::<Vec<_>>();
+  assert_eq!(calls, vec![("fs".to_string(), 0, 19)]);
+  let unanalyzable = graph
+    .unanalyzable_require_calls(&specifier)
+    .into_iter()
+    .map(|range| (range.start.line, range.start.character))
+    .collect::<Vec<_>>();
+  assert_eq!(unanalyzable, vec![(2, 13)]);
+}
+
+#[tokio::test]
+async fn graph_max_modules() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import './a.ts';\nimport './b.ts';")
+      .add_local_file("/a.ts", "import './c.ts';")
+      .add_local_file("/b.ts", "export {};")
+      .add_local_file("/c.ts", "export {};");
+  });
+
+  ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    max_modules: Some(4),
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+
+  let err_message = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    max_modules: Some(3),
+    ..test_builder.graph_options()
+  })
+  .await
+  .err()
+  .unwrap()
+  .to_string();
+  assert_eq!(
+    err_message,
+    concat!(
+      "The module graph exceeded the maximum of 3 modules (4 modules reached). ",
+      "Check for accidentally broad imports from the entry points:\n",
+      "  * file:///mod.ts",
+    )
+  );
+}
+
+#[tokio::test]
+async fn graph_dependency_range() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        "import { a } from './a.ts';\nexport * from './a.ts';",
+      )
+      .add_local_file("/a.ts", "export const a = 1;");
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let specifier = ModuleSpecifier::parse("file:///mod.ts").unwrap();
+  let range = graph.dependency_range(&specifier, "./a.ts").unwrap();
+  assert_eq!(range.specifier, specifier);
+  assert_eq!(
+    (
+      (range.start.line, range.start.character),
+      (range.end.line, range.end.character)
+    ),
+    ((0, 18), (0, 26))
+  );
+  assert_eq!(graph.dependency_range(&specifier, "./b.ts"), None);
+}
+
+#[tokio::test]
+async fn graph_node_modules_dir() {
+  let mut test_builder = TestBuilder::new();
+  test_builder
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/project/mod.ts",
+          concat!(
+            "import { value } from 'pkg';\n",
+            "import { a } from 'pkg/utils/a';\n",
+            "import './sub/mod.ts';\n",
+            "import 'shared';\n",
+          ),
+        )
+        .add_local_file("/project/sub/mod.ts", "import 'pkg';")
+        .add_local_file(
+          "/project/node_modules/pkg/package.json",
+          r#"{ "exports": { ".": { "import": "./index.mjs" }, "./utils/*": "./dist/utils/*.js" } }"#,
+        )
+        .add_local_file(
+          "/project/node_modules/pkg/index.mjs",
+          "export const value = 5;",
+        )
+        .add_local_file(
+          "/project/node_modules/pkg/dist/utils/a.js",
+          "export const a = 1;",
+        )
+        .add_local_file(
+          "/project/sub/node_modules/pkg/package.json",
+          r#"{ "main": "./main.js" }"#,
+        )
+        .add_local_file("/project/sub/node_modules/pkg/main.js", "export {};")
+        .add_local_file("/packages/shared/index.js", "export {};");
+    })
+    .entry_point("file:///project/mod.ts");
+
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    node_modules_dir: Some(PathBuf::from("/packages")),
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  let resolve = |value: &str, referrer: &str| {
+    graph
+      .resolve_dependency(value, &ModuleSpecifier::parse(referrer).unwrap())
+      .map(|s| s.to_string())
+  };
+  assert_eq!(
+    resolve("pkg", "file:///project/mod.ts").as_deref(),
+    Some("file:///project/node_modules/pkg/index.mjs")
+  );
+  assert_eq!(
+    resolve("pkg/utils/a", "file:///project/mod.ts").as_deref(),
+    Some("file:///project/node_modules/pkg/dist/utils/a.js")
+  );
+  // the closest node_modules directory is used
+  assert_eq!(
+    resolve("pkg", "file:///project/sub/mod.ts").as_deref(),
+    Some("file:///project/sub/node_modules/pkg/main.js")
+  );
+  // falls back to the configured directory
+  assert_eq!(
+    resolve("shared", "file:///project/mod.ts").as_deref(),
+    Some("file:///packages/shared/index.js")
+  );
+}
+
+#[tokio::test]
+async fn graph_node_modules_dir_invalid_package_json() {
+  let mut test_builder = TestBuilder::new();
+  test_builder
+    .with_loader(|loader| {
+      loader
+        .add_local_file("/project/mod.ts", "import 'pkg';")
+        .add_local_file("/project/node_modules/pkg/package.json", "{")
+        .add_local_file("/project/node_modules/pkg/index.js", "export {};");
+    })
+    .entry_point("file:///project/mod.ts");
+
+  let err = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    node_modules_dir: Some(PathBuf::from("/project/node_modules")),
+    ..test_builder.graph_options()
+  })
+  .await
+  .err()
+  .unwrap();
+  assert!(
+    format!("{:#}", err)
+      .contains("Error parsing file:///project/node_modules/pkg/package.json"),
+    "{:#}",
+    err
+  );
+}
+
+#[tokio::test]
+async fn graph_position_at() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader.add_local_file("/mod.ts", "const a = 'é';\nconst b = 2;");
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let specifier = ModuleSpecifier::parse("file:///mod.ts").unwrap();
+  assert_eq!(graph.position_at(&specifier, 0), Some((1, 1)));
+  assert_eq!(graph.position_at(&specifier, 6), Some((1, 7)));
+  // within the multi-byte character
+  assert_eq!(graph.position_at(&specifier, 12), None);
+  assert_eq!(graph.position_at(&specifier, 16), Some((2, 1)));
+  assert_eq!(graph.position_at(&specifier, 28), Some((2, 13)));
+  assert_eq!(graph.position_at(&specifier, 29), None);
+  let other = ModuleSpecifier::parse("file:///other.ts").unwrap();
+  assert_eq!(graph.position_at(&other, 0), None);
+}
+
+#[tokio::test]
+async fn graph_with_prelude() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        "#!/usr/bin/env -S deno run\n// comment\nconst a = 1;",
+      )
+      .add_local_file("/other.ts", "const a = 1;")
+      .add_local_file("/data.json", "{}");
+  });
+  test_builder
+    .add_entry_point("file:///other.ts")
+    .add_entry_point("file:///data.json");
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  assert_eq!(
+    graph.with_prelude(
+      &ModuleSpecifier::parse("file:///mod.ts").unwrap(),
+      "import * as dntShim from './_dnt.shims.js';"
+    ),
+    Some(
+      concat!(
+        "#!/usr/bin/env -S deno run\n// comment\n",
+        "import * as dntShim from './_dnt.shims.js';\n",
+        "\nconst a = 1;"
+      )
+      .to_string()
+    )
+  );
+  assert_eq!(
+    graph.with_prelude(
+      &ModuleSpecifier::parse("file:///other.ts").unwrap(),
+      "import * as dntShim from './_dnt.shims.js';"
+    ),
+    Some(
+      "import * as dntShim from './_dnt.shims.js';\nconst a = 1;".to_string()
+    )
+  );
+  assert_eq!(
+    graph.with_prelude(
+      &ModuleSpecifier::parse("file:///data.json").unwrap(),
+      "import * as dntShim from './_dnt.shims.js';"
+    ),
+    None
+  );
+}
+
+#[tokio::test]
+async fn graph_lockfile_package_versions() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        "import 'npm:chalk';\nimport 'npm:preact';\nimport 'npm:other@^1';",
+      )
+      .add_local_file(
+        "/deno.lock",
+        r#"{ "version": "4", "specifiers": { "npm:chalk@*": "5.3.0" } }"#,
+      );
+  });
+
+  let (_, specifiers) =
+    ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+      lockfile: Some(ModuleSpecifier::parse("file:///deno.lock").unwrap()),
+      default_package_version: Some("^10.0.0".to_string()),
+      ..test_builder.graph_options()
+    })
+    .await
+    .unwrap();
+  let versions = specifiers
+    .main
+    .mapped
+    .iter()
+    .map(|(k, v)| (k.to_string(), v.version.clone().unwrap()))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    versions,
+    vec![
+      ("npm:chalk".to_string(), "5.3.0".to_string()),
+      ("npm:other@^1".to_string(), "^1".to_string()),
+      ("npm:preact".to_string(), "^10.0.0".to_string()),
+    ]
+  );
+  let sources = specifiers
+    .package_version_sources()
+    .iter()
+    .map(|(k, v)| (k.to_string(), *v))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    sources,
+    vec![
+      ("npm:chalk".to_string(), PackageVersionSource::Lockfile),
+      ("npm:preact".to_string(), PackageVersionSource::Default),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_has_top_level_side_effects() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "\"use strict\";\n",
+          "import './pure.ts';\n",
+          "import './call.ts';\n",
+          "import './assign.ts';\n",
+          "import './static.ts';\n",
+          "export const a = { b: [1, 'c'], d: () => {} };\n",
+          "export function e() { console.log(1); }\n",
+          "export class F { static g = 1; }\n",
+          "export default 5 as number;\n",
+          "interface H {}\n",
+        ),
+      )
+      .add_local_file("/pure.ts", "export * from './mod.ts';")
+      .add_local_file("/call.ts", "export const a = setup();")
+      .add_local_file("/assign.ts", "globalThis.value = 1;")
+      .add_local_file("/static.ts", "class A { static { run(); } }");
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let has_side_effects = |path: &str| {
+    graph.has_top_level_side_effects(
+      &ModuleSpecifier::parse(&format!("file://{}", path)).unwrap(),
+    )
+  };
+  assert!(!has_side_effects("/mod.ts"));
+  assert!(!has_side_effects("/pure.ts"));
+  assert!(has_side_effects("/call.ts"));
+  assert!(has_side_effects("/assign.ts"));
+  assert!(has_side_effects("/static.ts"));
+  assert!(!has_side_effects("/missing.ts"));
+}
+
+#[tokio::test]
+async fn graph_unversioned_jsr_package() {
+  struct RegistryUrlProvider(ModuleSpecifier);
+
+  impl JsrUrlProvider for RegistryUrlProvider {
+    fn url(&self) -> &ModuleSpecifier {
+      &self.0
+    }
+  }
+
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import 'jsr:@scope/pkg';")
+      .add_remote_file(
+        "https://registry.example/@scope/pkg/meta.json",
+        r#"{ "versions": { "1.0.0": {}, "1.2.0": {}, "2.0.0": { "yanked": true } } }"#,
+      )
+      .add_remote_file(
+        "https://registry.example/@scope/pkg/1.2.0_meta.json",
+        r#"{ "exports": { ".": "./mod.ts" }, "manifest": {} }"#,
+      )
+      .add_remote_file(
+        "https://registry.example/@scope/pkg/1.2.0/mod.ts",
+        "export const a = 1;",
+      );
+  });
+
+  let url_provider = RegistryUrlProvider(
+    ModuleSpecifier::parse("https://registry.example/").unwrap(),
+  );
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    jsr_url_provider: Some(&url_provider),
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  let packages = graph
+    .jsr_packages()
+    .into_iter()
+    .map(|(req, nv)| (req.to_string(), nv.to_string()))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    packages,
+    vec![("@scope/pkg".to_string(), "@scope/pkg@1.2.0".to_string())]
+  );
+  assert!(graph.all_modules().any(|m| m.specifier().as_str()
+    == "https://registry.example/@scope/pkg/1.2.0/mod.ts"));
+}
+
+#[tokio::test]
+async fn graph_raw_import_specifiers() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "import './a.ts';\n",
+          "export * from \"./b\\u002ets\";\n",
+          "const c = await import('./a.ts');\n",
+        ),
+      )
+      .add_local_file("/a.ts", "export const a = 1;")
+      .add_local_file("/b.ts", "export { a } from './a.ts';");
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let specifiers = graph
+    .raw_import_specifiers()
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    specifiers,
+    vec![
+      ("file:///b.ts".to_string(), vec!["./a.ts".to_string()]),
+      (
+        "file:///mod.ts".to_string(),
+        vec![
+          "./a.ts".to_string(),
+          "./b\\u002ets".to_string(),
+          "./a.ts".to_string(),
+        ]
+      ),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_warn_import_map_bypass() {
+  let mut test_builder = TestBuilder::new();
+  test_builder
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/import_map.json",
+          r#"{
+  "imports": {
+    "std/": "https://deno.land/std@0.1.0/",
+    "preact": "https://esm.sh/preact",
+    "https://example.com/": "/vendor/"
+  }
+}"#,
+        )
+        .add_local_file(
+          "/mod.ts",
+          concat!(
+            "import 'https://deno.land/std@0.1.0/path/mod.ts';\n",
+            "import 'std/fs/mod.ts';\n",
+            "import 'https://esm.sh/preact';\n",
+            "import 'https://example.com/mod.ts';\n",
+          ),
+        )
+        .add_remote_file("https://deno.land/std@0.1.0/path/mod.ts", "")
+        .add_remote_file("https://deno.land/std@0.1.0/fs/mod.ts", "")
+        .add_remote_file_with_headers(
+          "https://esm.sh/preact",
+          "",
+          &[("content-type", "application/javascript")],
+        )
+        .add_local_file("/vendor/mod.ts", "");
+    })
+    .set_import_map("file:///import_map.json");
+
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    warn_import_map_bypass: true,
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  assert_eq!(
+    graph.warnings(),
+    &[
+      concat!(
+        "Import of https://deno.land/std@0.1.0/path/mod.ts bypasses the import map. ",
+        "Use \"std/path/mod.ts\" instead.\n",
+        "    at file:///mod.ts:1:8"
+      )
+      .to_string(),
+      concat!(
+        "Import of https://esm.sh/preact bypasses the import map. ",
+        "Use \"preact\" instead.\n",
+        "    at file:///mod.ts:3:8"
+      )
+      .to_string(),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_types_packages() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader.add_local_file(
+      "/mod.ts",
+      "import 'npm:lodash@^4';\nimport 'npm:@scope/pkg@1';\nimport 'npm:chalk@5';",
+    );
+  });
+
+  let (_, specifiers) =
+    ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+      types_packages: HashMap::from([
+        ("lodash".to_string(), "^4.14.0".to_string()),
+        ("@scope/pkg".to_string(), "1.0.0".to_string()),
+        ("unused".to_string(), "1.0.0".to_string()),
+      ]),
+      ..test_builder.graph_options()
+    })
+    .await
+    .unwrap();
+  assert_eq!(
+    specifiers.types_packages(),
+    &[
+      Dependency {
+        name: "@types/lodash".to_string(),
+        version: "^4.14.0".to_string(),
+        peer_dependency: false,
+      },
+      Dependency {
+        name: "@types/scope__pkg".to_string(),
+        version: "1.0.0".to_string(),
+        peer_dependency: false,
+      },
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_module_ids() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import './b.ts';\nimport './a.ts';")
+      .add_local_file("/a.ts", "export const a = 1;")
+      .add_local_file("/b.ts", "export const b = 1;");
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let ids = graph
+    .module_ids()
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    ids,
+    vec![
+      ("file:///a.ts".to_string(), 0),
+      ("file:///b.ts".to_string(), 1),
+      ("file:///mod.ts".to_string(), 2),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_specifier_vars() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import '${BASE}/${NAME}.ts';")
+      .add_local_file("/lib/a.ts", "export const a = 1;");
+  });
+
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    specifier_vars: HashMap::from([
+      ("BASE".to_string(), "./lib".to_string()),
+      ("NAME".to_string(), "a".to_string()),
+    ]),
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  assert!(graph
+    .all_modules()
+    .any(|m| m.specifier().as_str() == "file:///lib/a.ts"));
+
+  let err = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    specifier_vars: HashMap::from([("NAME".to_string(), "a".to_string())]),
+    ..test_builder.graph_options()
+  })
+  .await
+  .err()
+  .unwrap();
+  assert_eq!(
+    err.to_string(),
+    "Missing specifier variable BASE for ${BASE}/${NAME}.ts\n    at file:///mod.ts:1:8"
+  );
+}
+
+#[tokio::test]
+async fn graph_barrel_collisions() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "export * from './a.ts';\n",
+          "export * from './b.ts';\n",
+          "export * from './c.ts';\n",
+          "export const shadowed = 1;\n",
+        ),
+      )
+      .add_local_file(
+        "/a.ts",
+        concat!(
+          "export const value = 1;\n",
+          "export const shadowed = 1;\n",
+          "export default 1;\n",
+          "export * from './shared.ts';\n",
+        ),
+      )
+      .add_local_file(
+        "/b.ts",
+        concat!(
+          "export function value() {}\n",
+          "export { shadowed } from './c.ts';\n",
+          "export default 2;\n",
+          "export * from './shared.ts';\n",
+        ),
+      )
+      .add_local_file("/c.ts", "export const shadowed = 1, other = 2;")
+      .add_local_file("/shared.ts", "export const shared = 1;");
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  assert_eq!(
+    graph.barrel_collisions(&ModuleSpecifier::parse("file:///mod.ts").unwrap()),
+    vec![Collision {
+      name: "value".to_string(),
+      specifiers: vec![
+        ModuleSpecifier::parse("file:///a.ts").unwrap(),
+        ModuleSpecifier::parse("file:///b.ts").unwrap(),
+      ],
+    }]
+  );
+}
+
+#[tokio::test]
+async fn graph_uses_typescript_features() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "import './plain.js';\n",
+          "import './types.ts';\n",
+          "const enum A { B }\n",
+          "namespace C { export const d = 1; }\n",
+          "class E { constructor(private f) {} }\n",
+        ),
+      )
+      .add_local_file("/plain.js", "export class A { method(a = 1) {} }")
+      .add_local_file(
+        "/types.ts",
+        "enum A { B }\nexport const a = (1 as number);",
+      );
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let features = |path: &str| {
+    graph.uses_typescript_features(
+      &ModuleSpecifier::parse(&format!("file://{}", path)).unwrap(),
+    )
+  };
+  assert_eq!(
+    features("/mod.ts"),
+    TsFeatureSet {
+      const_enums: true,
+      namespaces: true,
+      parameter_properties: true,
+      ..Default::default()
+    }
+  );
+  assert_eq!(
+    features("/types.ts"),
+    TsFeatureSet {
+      type_annotations: true,
+      enums: true,
+      ..Default::default()
+    }
+  );
+  assert!(features("/plain.js").is_empty());
+}
+
+#[tokio::test]
+async fn graph_uses_typescript_features_constructs() {
+  let type_annotations = TsFeatureSet {
+    type_annotations: true,
+    ..Default::default()
+  };
+  let class_modifiers = TsFeatureSet {
+    class_modifiers: true,
+    ..Default::default()
+  };
+  let import_export_assignments = TsFeatureSet {
+    import_export_assignments: true,
+    ..Default::default()
+  };
+  let cases = [
+    (
+      "/implements.ts",
+      "class A implements B {}",
+      type_annotations,
+    ),
+    (
+      "/export_type.ts",
+      "export type { A } from './a.ts';",
+      type_annotations,
+    ),
+    (
+      "/import_type.ts",
+      "import { type A } from './a.ts';",
+      type_annotations,
+    ),
+    (
+      "/export_inline_type.ts",
+      "export { type A } from './a.ts';",
+      type_annotations,
+    ),
+    ("/declare.ts", "declare function a();", type_annotations),
+    (
+      "/import_equals.ts",
+      "import a = require('./a.js');",
+      import_export_assignments,
+    ),
+    (
+      "/export_assignment.ts",
+      "const a = 1;\nexport = a;",
+      import_export_assignments,
+    ),
+    ("/abstract_class.ts", "abstract class A {}", class_modifiers),
+    (
+      "/abstract_member.ts",
+      "abstract class A { abstract a; }",
+      class_modifiers,
+    ),
+    ("/private.ts", "class A { private a = 1; }", class_modifiers),
+    (
+      "/protected.ts",
+      "class A { protected a() {} }",
+      class_modifiers,
+    ),
+    (
+      "/public.ts",
+      "class A { public constructor() {} }",
+      class_modifiers,
+    ),
+    (
+      "/declare_field.ts",
+      "class A { declare a; }",
+      class_modifiers,
+    ),
+  ];
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader.add_local_file(
+      "/mod.ts",
+      cases
+        .iter()
+        .map(|(path, _, _)| format!("import '.{}';", path))
+        .collect::<Vec<_>>()
+        .join("\n"),
+    );
+    loader.add_local_file("/a.ts", "export type A = string;");
+    loader.add_local_file("/a.js", "module.exports = 1;");
+    for (path, text, _) in &cases {
+      loader.add_local_file(path, text);
+    }
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  for (path, _, expected) in &cases {
+    assert_eq!(
+      graph.uses_typescript_features(
+        &ModuleSpecifier::parse(&format!("file://{}", path)).unwrap(),
+      ),
+      *expected,
+      "{}",
+      path,
+    );
+  }
+}
+
+#[tokio::test]
+async fn graph_vendor_dir() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        "import 'https://deno.land/std/mod.ts';\nimport 'https://esm.sh/preact';",
+      )
+      .add_local_file(
+        "/vendor/import_map.json",
+        r#"{
+  "imports": {
+    "https://deno.land/": "./deno.land/",
+    "https://esm.sh/preact": "./esm.sh/preact.js"
+  }
+}"#,
+      )
+      .add_local_file("/vendor/deno.land/std/mod.ts", "export const a = 1;")
+      .add_local_file("/vendor/esm.sh/preact.js", "export const b = 1;");
+  });
+
+  let (graph, specifiers) =
+    ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+      vendor_dir: Some(ModuleSpecifier::parse("file:///vendor").unwrap()),
+      ..test_builder.graph_options()
+    })
+    .await
+    .unwrap();
+  let mut remote = specifiers
+    .remote
+    .iter()
+    .map(|s| s.to_string())
+    .collect::<Vec<_>>();
+  remote.sort();
+  assert_eq!(
+    remote,
+    vec![
+      "https://deno.land/std/mod.ts".to_string(),
+      "https://esm.sh/preact".to_string(),
+    ]
+  );
+  let preact = graph
+    .get(&ModuleSpecifier::parse("https://esm.sh/preact").unwrap())
+    .js()
+    .unwrap();
+  assert_eq!(preact.media_type, MediaType::JavaScript);
+  assert_eq!(preact.source.as_ref(), "export const b = 1;");
+
+  test_builder.with_loader(|loader| {
+    loader.add_local_file("/mod.ts", "import 'https://example.com/mod.ts';");
+  });
+  let err = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    vendor_dir: Some(ModuleSpecifier::parse("file:///vendor/").unwrap()),
+    ..test_builder.graph_options()
+  })
+  .await
+  .err()
+  .unwrap();
+  assert_eq!(
+    err.to_string(),
+    "https://example.com/mod.ts was not found in the vendor directory.\n    at file:///mod.ts:1:8"
+  );
+}
+
+#[tokio::test]
+async fn graph_unused_imports() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "import def, { a, b as c, type T, U, unused } from './a.ts';\n",
+          "import * as ns from './a.ts';\n",
+          "import { exported, shadowed } from './a.ts';\n",
+          "console.log(def, c);\n",
+          "const value: T = ns.a;\n",
+          "function f(shadowed: U) { return shadowed.a; }\n",
+          "export { exported };\n",
+        ),
+      )
+      .add_local_file(
+        "/a.ts",
+        concat!(
+          "export default 1;\n",
+          "export const a = 1, b = 2, unused = 3, exported = 4, shadowed = 5;\n",
+          "export type T = number;\n",
+          "export interface U { a: number }\n",
+        ),
+      );
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let unused = graph
+    .unused_imports(&ModuleSpecifier::parse("file:///mod.ts").unwrap())
+    .into_iter()
+    .map(|(name, range)| format!("{} {}", name, range))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    unused,
+    vec![
+      "a file:///mod.ts:1:15".to_string(),
+      "unused file:///mod.ts:1:37".to_string(),
+      "shadowed file:///mod.ts:3:20".to_string(),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_build_many() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/a/mod.ts", "import 'https://example.com/shared.ts';")
+      .add_local_file("/b/mod.ts", "import 'https://example.com/shared.ts';")
+      .add_local_file("/c/mod.ts", "import './missing.ts';")
+      .add_remote_file_with_headers(
+        "https://example.com/shared.ts",
+        "export const a = 1;",
+        &[("content-type", "application/typescript")],
+      );
+  });
+
+  let options_for = |entry_point: &str| ModuleGraphOptions {
+    entry_points: vec![ModuleSpecifier::parse(entry_point).unwrap()],
+    ..test_builder.graph_options()
+  };
+  let results = ModuleGraph::build_many(vec![
+    options_for("file:///a/mod.ts"),
+    options_for("file:///b/mod.ts"),
+    options_for("file:///c/mod.ts"),
+  ])
+  .await;
+  assert_eq!(results.len(), 3);
+  for (result, entry_point) in results.iter().zip(["/a/mod.ts", "/b/mod.ts"]) {
+    let (_, specifiers) = result.as_ref().unwrap();
+    assert_eq!(
+      specifiers.local,
+      vec![ModuleSpecifier::parse(&format!("file://{}", entry_point)).unwrap()]
+    );
+  }
+  assert!(results[2].is_err());
+  assert_eq!(
+    test_builder
+      .loader()
+      .load_count("https://example.com/shared.ts"),
+    1
+  );
+}
+
+#[tokio::test]
+async fn graph_build_many_concurrent_loads() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .set_remote_yields(10)
+      .add_local_file("/a/mod.ts", "import 'https://example.com/shared.ts';")
+      .add_local_file("/b/mod.ts", "import 'https://example.com/shared.ts';")
+      .add_remote_file_with_headers(
+        "https://example.com/shared.ts",
+        "export const a = 1;",
+        &[("content-type", "application/typescript")],
+      );
+  });
+
+  let options_for = |entry_point: &str| ModuleGraphOptions {
+    entry_points: vec![ModuleSpecifier::parse(entry_point).unwrap()],
+    ..test_builder.graph_options()
+  };
+  let results = ModuleGraph::build_many(vec![
+    options_for("file:///a/mod.ts"),
+    options_for("file:///b/mod.ts"),
+  ])
+  .await;
+  let cache_stats = results
+    .iter()
+    .map(|result| result.as_ref().unwrap().0.cache_stats())
+    .collect::<Vec<_>>();
+  // the second build waits on the load the first build started
+  assert_eq!(
+    test_builder
+      .loader()
+      .load_count("https://example.com/shared.ts"),
+    1
+  );
+  assert_eq!(cache_stats[0].hits, 0);
+  assert_eq!(cache_stats[1].hits, 1);
+}
+
+#[tokio::test]
+async fn graph_cache_stats() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/a/mod.ts", "import 'https://example.com/shared.ts';")
+      .add_local_file("/b/mod.ts", "import 'https://example.com/shared.ts';")
+      .add_remote_file_with_headers(
+        "https://example.com/shared.ts",
+        "export const a = 1;",
+        &[("content-type", "application/typescript")],
+      );
+  });
+
+  let options_for = |entry_point: &str| ModuleGraphOptions {
+    entry_points: vec![ModuleSpecifier::parse(entry_point).unwrap()],
+    ..test_builder.graph_options()
+  };
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(options_for("file:///a/mod.ts"))
+      .await
+      .unwrap();
+  // 39 bytes for the entry point and 19 for the remote module
+  assert_eq!(
+    graph.cache_stats(),
+    CacheStats {
+      hits: 0,
+      misses: 2,
+      bytes_served_from_cache: 0,
+      bytes_loaded: 58,
+    }
+  );
+
+  let stats = ModuleGraph::build_many(vec![
+    options_for("file:///a/mod.ts"),
+    options_for("file:///b/mod.ts"),
+  ])
+  .await
+  .into_iter()
+  .map(|result| result.unwrap().0.cache_stats())
+  .collect::<Vec<_>>();
+  assert_eq!(
+    stats,
+    vec![
+      CacheStats {
+        hits: 0,
+        misses: 2,
+        bytes_served_from_cache: 0,
+        bytes_loaded: 58,
+      },
+      CacheStats {
+        hits: 1,
+        misses: 1,
+        bytes_served_from_cache: 19,
+        bytes_loaded: 39,
+      },
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_media_type_counts() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "import './a.ts';\n",
+          "import './b.tsx';\n",
+          "import data from './data.json' with { type: 'json' };\n",
+          "import 'node:fs';\n",
+        ),
+      )
+      .add_local_file("/a.ts", "export const a = 1;")
+      .add_local_file("/b.tsx", "export const b = <div />;")
+      .add_local_file("/data.json", "{}");
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  assert_eq!(
+    graph.media_type_counts(),
+    BTreeMap::from([
+      (MediaType::TypeScript, 2),
+      (MediaType::Tsx, 1),
+      (MediaType::Json, 1),
+    ])
+  );
+}
+
+#[tokio::test]
+async fn graph_json_transform() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        "import data from './data.json' with { type: 'json' };",
+      )
+      .add_local_file("/data.json", r#"{ "a": 1, "secret": "value" }"#)
+      .add_local_file("/invalid.json", "{");
+  });
+
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    json_transform: Some(Box::new(|specifier, mut value| {
+      value.as_object_mut().unwrap().remove("secret");
+      value["source"] = specifier.to_string().into();
+      value
+    })),
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  let module = graph
+    .get(&ModuleSpecifier::parse("file:///data.json").unwrap())
+    .json()
+    .unwrap();
+  assert_eq!(
+    serde_json::from_str::<serde_json::Value>(&module.source).unwrap(),
+    serde_json::json!({ "a": 1, "source": "file:///data.json" })
+  );
+
+  // invalid json is left for deno_graph to handle
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    entry_points: vec![ModuleSpecifier::parse("file:///invalid.json").unwrap()],
+    json_transform: Some(Box::new(|_, _| serde_json::json!({}))),
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  let module = graph
+    .get(&ModuleSpecifier::parse("file:///invalid.json").unwrap())
+    .json()
+    .unwrap();
+  assert_eq!(module.source.as_ref(), "{");
+}
+
+#[tokio::test]
+async fn graph_import_meta_usages() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader.add_local_file(
+      "/mod.ts",
+      concat!(
+        "const dir = import.meta.dirname;\n",
+        "console.log(import.meta.filename, import.meta.url);\n",
+        "const other = { meta: 1 }.meta;\n",
+      ),
+    );
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let usages = graph
+    .import_meta_usages(&ModuleSpecifier::parse("file:///mod.ts").unwrap())
+    .into_iter()
+    .map(|usage| format!("{} {}", usage.name, usage.range))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    usages,
+    vec![
+      "dirname file:///mod.ts:1:13".to_string(),
+      "filename file:///mod.ts:2:13".to_string(),
+      "url file:///mod.ts:2:35".to_string(),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_json5_import_map() {
+  let mut test_builder = TestBuilder::new();
+  test_builder
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/import_map.json5",
+          "{\n  // comment\n  imports: {\n    'lib/': './lib/',\n  },\n}",
+        )
+        .add_local_file("/mod.ts", "import 'lib/a.ts';")
+        .add_local_file("/lib/a.ts", "export const a = 1;")
+        .add_local_file("/invalid.json5", "{ imports: { a: .5 } }");
+    })
+    .set_import_map("file:///import_map.json5");
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  assert!(graph
+    .all_modules()
+    .any(|m| m.specifier().as_str() == "file:///lib/a.ts"));
+
+  test_builder.set_import_map("file:///invalid.json5");
+  let err = ModuleGraph::build_with_specifiers(test_builder.graph_options())
+    .await
+    .err()
+    .unwrap();
+  assert_eq!(
+    format!("{:#}", err).split(": ").take(2).collect::<Vec<_>>(),
+    vec![
+      "Error loading import map.",
+      concat!(
+        "Error parsing file:///invalid.json5. Import maps are parsed as JSONC, ",
+        "which supports comments, trailing commas, single quoted strings, and ",
+        "unquoted property names, but not the rest of JSON5."
+      ),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_resolved_dependencies() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        "import './a.ts';\nimport 'node:fs';\nimport 'https://example.com/mod.ts';",
+      )
+      .add_local_file("/a.ts", "export const a = 1;")
+      .add_remote_file_with_headers(
+        "https://example.com/mod.ts",
+        "export const b = 1;",
+        &[("content-type", "application/typescript")],
+      );
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let mod_specifier = ModuleSpecifier::parse("file:///mod.ts").unwrap();
+  let resolved = graph.resolved_dependencies(&mod_specifier);
+  assert_eq!(
+    resolved,
+    vec![
+      (
+        "./a.ts".to_string(),
+        Some(ModuleSpecifier::parse("file:///a.ts").unwrap())
+      ),
+      ("node:fs".to_string(), None),
+      (
+        "https://example.com/mod.ts".to_string(),
+        Some(ModuleSpecifier::parse("https://example.com/mod.ts").unwrap())
+      ),
+    ]
+  );
+  for (value, specifier) in resolved {
+    assert_eq!(graph.resolve_dependency(&value, &mod_specifier), specifier);
+  }
+  assert!(graph
+    .resolved_dependencies(&ModuleSpecifier::parse("file:///a.ts").unwrap())
+    .is_empty());
+}
+
+#[tokio::test]
+async fn graph_skip_failed_entry_points() {
+  let mut test_builder = TestBuilder::new();
+  test_builder
+    .with_loader(|loader| {
+      loader
+        .add_local_file("/mod.ts", "import 'npm:chalk@5';")
+        .add_local_file(
+          "/bad.ts",
+          "import 'npm:preact@10';\nimport './missing.ts';",
+        );
+    })
+    .add_entry_point("file:///bad.ts");
+
+  let (graph, specifiers) =
+    ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+      skip_failed_entry_points: true,
+      ..test_builder.graph_options()
+    })
+    .await
+    .unwrap();
+  assert_eq!(
+    graph.failed_entry_points(),
+    &[ModuleSpecifier::parse("file:///bad.ts").unwrap()]
+  );
+  assert_eq!(
+    specifiers.local,
+    vec![ModuleSpecifier::parse("file:///mod.ts").unwrap()]
+  );
+  assert_eq!(
+    specifiers
+      .main
+      .mapped
+      .keys()
+      .chain(specifiers.test.mapped.keys())
+      .map(|s| s.to_string())
+      .collect::<Vec<_>>(),
+    vec!["npm:chalk@5".to_string()]
+  );
+
+  // errors when not skipping
+  assert!(
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .is_err()
+  );
+}
+
+#[tokio::test]
+async fn graph_find_cycles_by_kind() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "import './runtime_a.ts';\n",
+          "import './types_a.ts';\n",
+          "import './mixed_a.ts';\n",
+        ),
+      )
+      .add_local_file("/runtime_a.ts", "import './runtime_b.ts';")
+      .add_local_file("/runtime_b.ts", "import './runtime_a.ts';")
+      .add_local_file(
+        "/types_a.ts",
+        "import type { B } from './types_b.ts';\nexport type A = B;",
+      )
+      .add_local_file(
+        "/types_b.ts",
+        "import type { A } from './types_a.ts';\nexport type B = A;",
+      )
+      .add_local_file(
+        "/mixed_a.ts",
+        "import { b } from './mixed_b.ts';\nexport type A = string;",
+      )
+      .add_local_file(
+        "/mixed_b.ts",
+        "import type { A } from './mixed_a.ts';\nexport const b = 1;",
+      );
+  });
+
+  let graph = ModuleGraph::build_with_specifiers(test_builder.graph_options())
+    .await
+    .unwrap()
+    .0;
+  let specifier =
+    |path: &str| ModuleSpecifier::parse(&format!("file://{}", path)).unwrap();
+  assert_eq!(
+    graph.find_cycles_by_kind(),
+    vec![
+      Cycle {
+        specifiers: vec![specifier("/mixed_a.ts"), specifier("/mixed_b.ts")],
+        kind: CycleKind::Mixed,
+      },
+      Cycle {
+        specifiers: vec![
+          specifier("/runtime_a.ts"),
+          specifier("/runtime_b.ts")
+        ],
+        kind: CycleKind::Runtime,
+      },
+      Cycle {
+        specifiers: vec![specifier("/types_a.ts"), specifier("/types_b.ts")],
+        kind: CycleKind::TypeOnly,
+      },
+    ]
+  );
+  assert_eq!(
+    graph.find_cycles(),
+    vec![
+      vec![specifier("/mixed_a.ts"), specifier("/mixed_b.ts")],
+      vec![specifier("/runtime_a.ts"), specifier("/runtime_b.ts")],
+      vec![specifier("/types_a.ts"), specifier("/types_b.ts")],
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_package_mapping_output_sub_path() {
+  let mut test_builder = TestBuilder::new();
+  test_builder
+    .with_loader(|loader| {
+      loader.add_local_file(
+        "/mod.ts",
+        "import { foo } from 'https://deno.land/x/foo/mod.ts';",
+      );
+    })
+    .add_package_specifier_mapping(
+      "https://deno.land/x/foo/mod.ts",
+      "@org/foo",
+      Some("^1.0.0"),
+      Some("main"),
+    );
+
+  let (_, specifiers) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  assert_eq!(
+    specifiers.main.output_specifiers(),
+    BTreeMap::from([(
+      ModuleSpecifier::parse("https://deno.land/x/foo/mod.ts").unwrap(),
+      "@org/foo/main".to_string(),
+    )])
+  );
+
+  let result = test_builder.transform().await.unwrap();
+  assert_files!(
+    result.main.files,
+    &[("mod.ts", "import { foo } from '@org/foo/main';")]
+  );
+}
+
+#[tokio::test]
+async fn graph_wasm_module_info() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        "import { add } from './add.wasm';\nimport './empty.wasm';",
+      )
+      .add_local_file(
+        "/add.wasm",
+        concat!(
+          "\0asm\x01\0\0\0",
+          "\x01\x07\x01\x60\x02\x7f\x7f\x01\x7f",
+          "\x02\x10\x01\x08./env.js\x03log\x00\x00",
+          "\x03\x02\x01\x00",
+          "\x07\x07\x01\x03add\x00\x01",
+          "\x0a\x09\x01\x07\x00\x20\x00\x20\x01\x6a\x0b",
+        ),
+      )
+      .add_local_file(
+        "/empty.wasm",
+        concat!(
+          "\0asm\x01\0\0\0",
+          "\x01\x07\x01\x60\x02\x7f\x7f\x01\x7f",
+          "\x03\x02\x01\x00",
+          "\x07\x07\x01\x03add\x00\x00",
+          "\x0a\x09\x01\x07\x00\x20\x00\x20\x01\x6a\x0b",
+        ),
+      );
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let add_export = WasmExport {
+    name: "add".to_string(),
+    kind: WasmExternalKind::Function,
+  };
+  assert_eq!(
+    graph
+      .wasm_module_info(&ModuleSpecifier::parse("file:///add.wasm").unwrap()),
+    Some(WasmModuleInfo {
+      imports: vec![WasmImport {
+        module: "./env.js".to_string(),
+        name: "log".to_string(),
+        kind: WasmExternalKind::Function,
+      }],
+      exports: vec![add_export.clone()],
+    })
+  );
+  assert_eq!(
+    graph
+      .wasm_module_info(&ModuleSpecifier::parse("file:///empty.wasm").unwrap()),
+    Some(WasmModuleInfo {
+      imports: Vec::new(),
+      exports: vec![add_export],
+    })
+  );
+  assert_eq!(
+    graph.wasm_module_info(&ModuleSpecifier::parse("file:///mod.ts").unwrap()),
+    None
+  );
+}
+
+#[tokio::test]
+async fn graph_cdn_normalization() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "import 'https://unpkg.com/preact@10.0.0/index.js';\n",
+          "import 'https://ga.jspm.io/npm:preact@10.0.0/index.js';\n",
+          "import 'https://ga.jspm.io/npm:hooks@1.0.0/index.js';\n",
+        ),
+      )
+      .add_remote_file_with_headers(
+        "https://unpkg.com/preact@10.0.0/index.js",
+        "export const h = 1;",
+        &[("content-type", "application/javascript")],
+      )
+      .add_remote_file_with_headers(
+        "https://unpkg.com/hooks@1.0.0/index.js",
+        "import '/preact@10.0.0/index.js';",
+        &[("content-type", "application/javascript")],
+      );
+  });
+
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    cdn_normalization: vec![(
+      Regex::new(r"^https://ga\.jspm\.io/npm:(.+)$").unwrap(),
+      "https://unpkg.com/$1".to_string(),
+    )],
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  let mut specifiers = graph
+    .all_modules()
+    .map(|m| m.specifier().to_string())
+    .collect::<Vec<_>>();
+  specifiers.sort();
+  assert_eq!(
+    specifiers,
+    vec![
+      "file:///mod.ts",
+      "https://unpkg.com/hooks@1.0.0/index.js",
+      "https://unpkg.com/preact@10.0.0/index.js",
+    ]
+  );
+  assert_eq!(
+    test_builder
+      .loader()
+      .load_count("https://unpkg.com/preact@10.0.0/index.js"),
+    1
+  );
+}
+
+#[tokio::test]
+async fn graph_parse_diagnostics() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import './a.js';\nexport const a = 1;")
+      .add_local_file("/a.js", "const value = 1;\ndelete value;");
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let a_specifier = ModuleSpecifier::parse("file:///a.js").unwrap();
+  let diagnostics = graph.parse_diagnostics();
+  assert_eq!(diagnostics.keys().collect::<Vec<_>>(), vec![&a_specifier]);
+  assert_eq!(
+    diagnostics[&a_specifier]
+      .iter()
+      .map(|d| format!("{} {}", d.message, d.range))
+      .collect::<Vec<_>>(),
+    vec![
+      "'delete' cannot be called on an identifier in strict mode file:///a.js:2:8"
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_slow_type_diagnostics() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "import './other.js';\n",
+          "export function add(a: number, b = 1) { return a + b; }\n",
+          "export function typed(a: number): number { return a; }\n",
+          "export function overload(a: string): string;\n",
+          "export function overload(a: any) { return a; }\n",
+          "export const value = getValue();\n",
+          "export const literal = 'text';\n",
+          "export const arrow = (a) => a;\n",
+          "export class Class {\n",
+          "  prop = getValue();\n",
+          "  private hidden = getValue();\n",
+          "  constructor(public name: string, other) {}\n",
+          "  method(): void {}\n",
+          "  set value(value: string) {}\n",
+          "}\n",
+          "export default getValue();\n",
+          "function getValue(): number { return 1; }\n",
+        ),
+      )
+      .add_local_file("/other.js", "export const value = getValue();");
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let diagnostics = graph.slow_type_diagnostics();
+  assert_eq!(
+    diagnostics
+      .iter()
+      .map(|d| format!("{} {}", d.message(), d.range))
+      .collect::<Vec<_>>(),
+    vec![
+      "Missing an explicit return type for add. file:///mod.ts:2:17",
+      "Missing an explicit type for value. file:///mod.ts:6:14",
+      "Missing an explicit type for a parameter of arrow. file:///mod.ts:8:23",
+      "Missing an explicit return type for arrow. file:///mod.ts:8:14",
+      "Missing an explicit type for Class.prop. file:///mod.ts:10:3",
+      "Missing an explicit type for a parameter of Class.constructor. file:///mod.ts:12:36",
+      "The default export must be a declaration, identifier, or literal to have an explicit type. file:///mod.ts:16:16",
+    ]
+  );
+  assert_eq!(diagnostics[0].kind, SlowTypeKind::MissingReturnType);
+}
+
+#[tokio::test]
+async fn graph_reporter() {
+  #[derive(Debug, Default)]
+  struct TestReporter(RefCell<Vec<(String, usize, usize)>>);
+
+  impl Reporter for TestReporter {
+    fn on_load(
+      &self,
+      specifier: &ModuleSpecifier,
+      modules_done: usize,
+      modules_total: usize,
+    ) {
+      self.0.borrow_mut().push((
+        specifier.to_string(),
+        modules_done,
+        modules_total,
+      ));
+    }
+  }
+
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import './a.ts';\nimport './b.ts';")
+      .add_local_file("/a.ts", "export const a = 1;")
+      .add_local_file("/b.ts", "export const b = 1;");
+  });
+
+  let reporter = TestReporter::default();
+  ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    reporter: Some(&reporter),
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  assert_eq!(
+    reporter.0.take(),
+    vec![
+      ("file:///mod.ts".to_string(), 1, 3),
+      ("file:///a.ts".to_string(), 2, 3),
+      ("file:///b.ts".to_string(), 3, 3),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_reresolve() {
+  #[derive(Debug)]
+  struct AliasResolver(&'static str);
+
+  impl Resolver for AliasResolver {
+    fn resolve(
+      &self,
+      specifier_text: &str,
+      referrer_range: &deno_graph::Range,
+      _mode: deno_graph::source::ResolutionMode,
+    ) -> Result<ModuleSpecifier, deno_graph::source::ResolveError> {
+      let specifier_text = match specifier_text {
+        "alias" => self.0,
+        specifier_text => specifier_text,
+      };
+      Ok(deno_graph::resolve_import(
+        specifier_text,
+        &referrer_range.specifier,
+      )?)
+    }
+  }
+
+  let mut test_builder = TestBuilder::new();
+  test_builder
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          concat!(
+            "import 'alias';\n",
+            "import './b.ts';\n",
+            "import 'https://localhost/${NAME}/mod.ts';\n",
+          ),
+        )
+        .add_local_file("/a.ts", "export const a = 1;")
+        .add_local_file("/b.ts", "export const b = 1;")
+        .add_local_file("/c.ts", "export const c = 1;")
+        .add_local_file("/d.ts", "export const d = 1;")
+        .add_local_file(
+          "/import_map.json",
+          r#"{ "imports": { "alias": "./a.ts" } }"#,
+        );
+    })
+    .set_import_map("file:///import_map.json")
+    .add_module_specifier_mapping("https://localhost/c/mod.ts", "file:///c.ts");
+
+  let (mut graph, specifiers) =
+    ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+      specifier_vars: HashMap::from([("NAME".to_string(), "c".to_string())]),
+      ..test_builder.graph_options()
+    })
+    .await
+    .unwrap();
+  let mod_specifier = ModuleSpecifier::parse("file:///mod.ts").unwrap();
+  let alias_resolution = |graph: &ModuleGraph| {
+    graph
+      .resolve_dependency("alias", &mod_specifier)
+      .map(|s| s.to_string())
+  };
+  let external_dependencies = |graph: &ModuleGraph, specifiers: &Specifiers| {
+    graph
+      .external_dependencies(specifiers)
+      .into_iter()
+      .map(|d| {
+        let mapped = match d.mapped {
+          Some(MappedSpecifier::Package(package)) => package.name,
+          Some(MappedSpecifier::Module(specifier)) => specifier.to_string(),
+          None => "".to_string(),
+        };
+        (d.specifier.to_string(), mapped)
+      })
+      .collect::<Vec<_>>()
+  };
+  let local_specifiers = |specifiers: &Specifiers| {
+    let mut local = specifiers
+      .local
+      .iter()
+      .map(|s| s.to_string())
+      .collect::<Vec<_>>();
+    local.sort();
+    local
+  };
+  assert_eq!(alias_resolution(&graph).unwrap(), "file:///a.ts");
+  assert_eq!(
+    local_specifiers(&specifiers),
+    vec![
+      "file:///a.ts",
+      "file:///b.ts",
+      "file:///c.ts",
+      "file:///mod.ts"
+    ]
+  );
+
+  let specifiers = graph.reresolve(&AliasResolver("./b.ts")).await.unwrap();
+  assert_eq!(alias_resolution(&graph).unwrap(), "file:///b.ts");
+  assert_eq!(
+    local_specifiers(&specifiers),
+    vec!["file:///b.ts", "file:///c.ts", "file:///mod.ts"]
+  );
+  assert_eq!(test_builder.loader().load_count("file:///mod.ts"), 1);
+
+  // the specifier variables and mappings still apply
+  let specifiers = graph
+    .reresolve(&AliasResolver("npm:preact@10"))
+    .await
+    .unwrap();
+  assert_eq!(alias_resolution(&graph).unwrap(), "npm:preact@10");
+  assert_eq!(
+    external_dependencies(&graph, &specifiers),
+    vec![
+      (
+        "https://localhost/c/mod.ts".to_string(),
+        "file:///c.ts".to_string()
+      ),
+      ("npm:preact@10".to_string(), "preact".to_string()),
+    ]
+  );
+
+  // modules that weren't loaded require a rebuild
+  let err = graph.reresolve(&AliasResolver("./d.ts")).await.unwrap_err();
+  assert!(err
+    .to_string()
+    .contains("The graph needs to be rebuilt to load modules"));
+  assert_eq!(alias_resolution(&graph).unwrap(), "npm:preact@10");
+}
+
+#[tokio::test]
+async fn transform_node_specifier_mappings() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          concat!(
+            "import * as fs from 'node:fs';\n",
+            "import * as path from 'node:path';\n",
+            "import * as util from 'node:util';\n",
+          ),
+        )
+        .add_local_file("/util_shim.ts", "export const inspect = 1;");
+    })
+    .add_package_specifier_mapping("node:fs", "memfs", Some("^4.0.0"), None)
+    .add_module_specifier_mapping("node:util", "file:///util_shim.ts")
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[
+      (
+        "mod.ts",
+        concat!(
+          "import * as fs from 'memfs';\n",
+          "import * as path from 'node:path';\n",
+          "import * as util from './util_shim.js';\n",
+        )
+      ),
+      ("util_shim.ts", "export const inspect = 1;"),
+    ]
+  );
+  assert_eq!(
+    result.main.dependencies,
+    &[Dependency {
+      name: "memfs".to_string(),
+      version: "^4.0.0".to_string(),
+      peer_dependency: false,
+    }]
+  );
+}
+
+#[tokio::test]
+async fn graph_symbol_graph() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "import { a as x, b, helper, default as d } from './barrel.ts';\n",
+          "import * as ns from './a.ts';\n",
+          "import { missing } from './barrel.ts';\n",
+          "console.log(x, b, helper, d, ns, missing);\n",
+        ),
+      )
+      .add_local_file(
+        "/barrel.ts",
+        concat!(
+          "export * from './a.ts';\n",
+          "export { b } from './b.ts';\n",
+          "import def from './c.ts';\n",
+          "export { def as default };\n",
+        ),
+      )
+      .add_local_file(
+        "/a.ts",
+        "export const a = 1;\nexport function helper() {}\n",
+      )
+      .add_local_file("/b.ts", "const b = 2;\nexport { b };\n")
+      .add_local_file("/c.ts", "export default function () {}\n");
+  });
+
+  let graph = ModuleGraph::build_with_specifiers(test_builder.graph_options())
+    .await
+    .unwrap()
+    .0;
+  let symbol_graph = graph.symbol_graph();
+  let specifier =
+    |path: &str| ModuleSpecifier::parse(&format!("file://{}", path)).unwrap();
+  let imports = symbol_graph.modules[&specifier("/mod.ts")]
+    .imports
+    .iter()
+    .map(|import| {
+      (
+        import.local.as_str(),
+        import.imported.as_str(),
+        import
+          .declaration
+          .as_ref()
+          .map(|d| (d.range.specifier.path(), d.name.as_str())),
+      )
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(
+    imports,
+    vec![
+      ("x", "a", Some(("/a.ts", "a"))),
+      ("b", "b", Some(("/b.ts", "b"))),
+      ("helper", "helper", Some(("/a.ts", "helper"))),
+      ("d", "default", Some(("/c.ts", "default"))),
+      ("ns", "*", None),
+      ("missing", "missing", None),
+    ]
+  );
+
+  let barrel_exports = symbol_graph.modules[&specifier("/barrel.ts")]
+    .exports
+    .iter()
+    .map(|export| export.name.as_str())
+    .collect::<Vec<_>>();
+  assert_eq!(barrel_exports, vec!["b", "default"]);
+
+  let declaration = symbol_graph.modules[&specifier("/a.ts")].exports[0]
+    .declaration
+    .clone()
+    .unwrap();
+  assert_eq!(declaration.range.start.line, 0);
+  assert_eq!(declaration.range.start.character, 13);
+  let references = symbol_graph
+    .references(&declaration.range)
+    .into_iter()
+    .map(|import| import.local.as_str())
+    .collect::<Vec<_>>();
+  assert_eq!(references, vec!["x"]);
+}
+
+#[tokio::test]
+async fn graph_symbol_graph_scopes() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "import { a } from './a.ts';\n",
+          "const helper = 1;\n",
+          "function f(a: number, helper: number) {\n",
+          "  const f = a + helper;\n",
+          "  return f;\n",
+          "}\n",
+          "export { a, helper };\n",
+          "export default f;\n",
+        ),
+      )
+      .add_local_file("/a.ts", "export const a = 1;\n");
+  });
+
+  let graph = ModuleGraph::build_with_specifiers(test_builder.graph_options())
+    .await
+    .unwrap()
+    .0;
+  let symbol_graph = graph.symbol_graph();
+  let exports = symbol_graph.modules
+    [&ModuleSpecifier::parse("file:///mod.ts").unwrap()]
+    .exports
+    .iter()
+    .map(|export| {
+      let declaration = export.declaration.as_ref().unwrap();
+      (
+        export.name.as_str(),
+        declaration.range.specifier.path(),
+        declaration.name.as_str(),
+        declaration.range.start.line,
+      )
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(
+    exports,
+    vec![
+      ("a", "/a.ts", "a", 0),
+      ("helper", "/mod.ts", "helper", 1),
+      ("default", "/mod.ts", "f", 2),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_platform_suffix() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "import './fs.ts';\n",
+          "import './path.ts';\n",
+          "import './fs.node.ts';\n",
+        ),
+      )
+      .add_local_file("/fs.ts", "export const platform = 'deno';")
+      .add_local_file("/fs.node.ts", "export const platform = 'node';")
+      .add_local_file("/path.ts", "export const platform = 'deno';")
+      .add_local_file("/path.browser.ts", "export const platform = 'browser';");
+  });
+
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    platform_suffix: Some("node".to_string()),
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  let mut specifiers = graph
+    .all_modules()
+    .map(|m| m.specifier().to_string())
+    .collect::<Vec<_>>();
+  specifiers.sort();
+  assert_eq!(
+    specifiers,
+    vec!["file:///fs.node.ts", "file:///mod.ts", "file:///path.ts"]
+  );
+  assert_eq!(
+    graph.resolve(&ModuleSpecifier::parse("file:///fs.ts").unwrap()),
+    ModuleSpecifier::parse("file:///fs.node.ts").unwrap(),
+  );
+  assert_eq!(
+    test_builder.loader().load_count("file:///fs.node.node.ts"),
+    0
+  );
+}
+
+#[tokio::test]
+async fn graph_platform_suffix_probe_error() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import './fs.ts';\nimport './path.ts';")
+      .add_local_file("/fs.ts", "export const platform = 'deno';")
+      .add_local_file_with_error("/fs.node.ts", "No such file or directory")
+      .add_local_file("/path.ts", "export const platform = 'deno';")
+      .add_local_file("/path.node.ts", "export const platform = 'node';");
+  });
+  let specifier =
+    |path: &str| ModuleSpecifier::parse(&format!("file://{}", path)).unwrap();
+  // digest of /path.node.ts rather than /path.ts
+  let integrity =
+    "sha256-eAkcoacYJEBOSqyLyr7R4jsim36Zaic7GRq+JlC+54c=".to_string();
+
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    platform_suffix: Some("node".to_string()),
+    integrity: [(specifier("/path.node.ts"), integrity)]
+      .into_iter()
+      .collect(),
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  let mut specifiers = graph
+    .all_modules()
+    .map(|m| m.specifier().to_string())
+    .collect::<Vec<_>>();
+  specifiers.sort();
+  assert_eq!(
+    specifiers,
+    vec!["file:///fs.ts", "file:///mod.ts", "file:///path.node.ts"]
+  );
+
+  let err_message = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    platform_suffix: Some("node".to_string()),
+    integrity: [(
+      specifier("/path.node.ts"),
+      "sha256-AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=".to_string(),
+    )]
+    .into_iter()
+    .collect(),
+    ..test_builder.graph_options()
+  })
+  .await
+  .err()
+  .unwrap()
+  .to_string();
+  assert!(
+    err_message.contains("Failed integrity check for file:///path.node.ts"),
+    "{}",
+    err_message
+  );
+}
+
+#[tokio::test]
+async fn transform_jsr_specifiers() {
+  let mut test_builder = TestBuilder::new();
+  test_builder
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          concat!(
+            "import { a } from 'jsr:@scope/pkg@^1';\n",
+            "import { b } from 'jsr:@scope/pkg@^1/sub';\n",
+            "console.log(a, b);",
+          ),
+        )
+        .add_remote_file(
+          "https://registry.example/@scope/pkg/meta.json",
+          r#"{ "versions": { "1.0.0": {}, "1.2.0": {} } }"#,
+        )
+        .add_remote_file(
+          "https://registry.example/@scope/pkg/1.2.0_meta.json",
+          r#"{ "exports": { ".": "./mod.ts", "./sub": "./sub.ts" }, "manifest": {} }"#,
+        )
+        .add_remote_file(
+          "https://registry.example/@scope/pkg/1.2.0/mod.ts",
+          "export * from './other.ts';",
+        )
+        .add_remote_file(
+          "https://registry.example/@scope/pkg/1.2.0/other.ts",
+          "export const a = 1;",
+        )
+        .add_remote_file(
+          "https://registry.example/@scope/pkg/1.2.0/sub.ts",
+          "export const b = 2;",
+        );
+    })
+    .set_jsr_url("https://registry.example/");
+
+  let result = test_builder.transform().await.unwrap();
+  assert_files!(
+    result.main.files,
+    &[
+      (
+        "mod.ts",
+        concat!(
+          "import { a } from './deps/registry.example/@scope/pkg/1.2.0/mod.js';\n",
+          "import { b } from './deps/registry.example/@scope/pkg/1.2.0/sub.js';\n",
+          "console.log(a, b);",
+        )
+      ),
+      (
+        "deps/registry.example/@scope/pkg/1.2.0/mod.ts",
+        "export * from './other.js';"
+      ),
+      (
+        "deps/registry.example/@scope/pkg/1.2.0/other.ts",
+        "export const a = 1;"
+      ),
+      (
+        "deps/registry.example/@scope/pkg/1.2.0/sub.ts",
+        "export const b = 2;"
+      ),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_syntax_options() {
+  let mut test_builder = TestBuilder::new();
+  test_builder
+    .entry_point("file:///mod.js")
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.js",
+          concat!(
+            "/** @jsx h */\n",
+            "import { h } from './h.js';\n",
+            "export const el = <div class=\"a\" />;\n",
+          ),
+        )
+        .add_local_file("/h.js", "export function h() {}");
+    });
+
+  // without the override the jsx is a recoverable parse error
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let diagnostics = graph.parse_diagnostics();
+  assert_eq!(
+    diagnostics[&ModuleSpecifier::parse("file:///mod.js").unwrap()][0].message,
+    "Expression expected"
+  );
+
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    syntax: SyntaxOptions {
+      jsx: true,
+      decorators: None,
+    },
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  assert!(graph.parse_diagnostics().is_empty());
+  assert_eq!(
+    graph.resolved_dependencies(
+      &ModuleSpecifier::parse("file:///mod.js").unwrap()
+    ),
+    vec![(
+      "./h.js".to_string(),
+      Some(ModuleSpecifier::parse("file:///h.js").unwrap())
+    )]
+  );
+}
+
+#[tokio::test]
+async fn graph_referenced_libs() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "/// <reference lib=\"dom\" />\n",
+          "/// <reference lib='DOM.Iterable' />\n",
+          "/// <reference types=\"./types.d.ts\" />\n",
+          "import './other.ts';\n",
+          "/// <reference lib=\"esnext\" />\n",
+        ),
+      )
+      .add_local_file("/types.d.ts", "export {};")
+      .add_local_file(
+        "/other.ts",
+        "/// <reference lib=\"dom\" />\n/// <reference lib=\"webworker\" />\n",
+      );
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let mut libs = graph.referenced_libs().into_iter().collect::<Vec<_>>();
+  libs.sort();
+  assert_eq!(libs, vec!["dom", "dom.iterable", "webworker"]);
+}
+
+#[tokio::test]
+async fn graph_index_resolution() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "import './utils';\n",
+          "import './components/';\n",
+          "import './data.js';\n",
+        ),
+      )
+      .add_local_file("/utils/index.ts", "export const a = 1;")
+      .add_local_file("/utils/index.js", "export const a = 1;")
+      .add_local_file("/components/index.js", "export const b = 1;")
+      .add_local_file("/data.js", "export const c = 1;");
+  });
+
+  // off by default
+  let err = ModuleGraph::build_with_specifiers(test_builder.graph_options())
+    .await
+    .err()
+    .unwrap();
+  assert!(err.to_string().contains("file:///utils"), "{:#}", err);
+
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    index_resolution: true,
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  let mut specifiers = graph
+    .all_modules()
+    .map(|m| m.specifier().to_string())
+    .collect::<Vec<_>>();
+  specifiers.sort();
+  assert_eq!(
+    specifiers,
+    vec![
+      "file:///components/index.js",
+      "file:///data.js",
+      "file:///mod.ts",
+      "file:///utils/index.ts",
+    ]
+  );
+  assert_eq!(
+    graph.resolve_dependency(
+      "./utils",
+      &ModuleSpecifier::parse("file:///mod.ts").unwrap()
+    ),
+    Some(ModuleSpecifier::parse("file:///utils/index.ts").unwrap()),
+  );
+
+  // the configured extension order is used
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    index_resolution: true,
+    index_extensions: vec!["js".to_string(), "ts".to_string()],
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  assert_eq!(
+    graph.resolve_dependency(
+      "./utils",
+      &ModuleSpecifier::parse("file:///mod.ts").unwrap()
+    ),
+    Some(ModuleSpecifier::parse("file:///utils/index.js").unwrap()),
+  );
+}
+
+#[tokio::test]
+async fn graph_index_resolution_without_index() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import './utils';")
+      .add_local_file("/utils/other.ts", "export const a = 1;");
+  });
+
+  let err = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    index_resolution: true,
+    ..test_builder.graph_options()
+  })
+  .await
+  .err()
+  .unwrap();
+  assert_eq!(
+    err.to_string(),
+    "Module not found \"file:///utils\".\n    at file:///mod.ts:1:8"
+  );
+}
+
+#[tokio::test]
+async fn graph_index_resolution_probe_error() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import './utils';")
+      .add_local_file_with_error("/utils", "Is a directory")
+      .add_local_file_with_error("/utils/index.ts", "Permission denied")
+      .add_local_file("/utils/index.js", "export const a = 1;");
+  });
+
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    index_resolution: true,
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  assert_eq!(
+    graph.resolve_dependency(
+      "./utils",
+      &ModuleSpecifier::parse("file:///mod.ts").unwrap()
+    ),
+    Some(ModuleSpecifier::parse("file:///utils/index.js").unwrap()),
+  );
+
+  // the original error is kept when no index file loads
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import './utils';")
+      .add_local_file_with_error("/utils", "Is a directory")
+      .add_local_file_with_error("/utils/index.ts", "Permission denied");
+  });
+  let err = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    index_resolution: true,
+    ..test_builder.graph_options()
+  })
+  .await
+  .err()
+  .unwrap()
+  .to_string();
+  assert!(err.contains("Is a directory"), "{}", err);
+  assert!(!err.contains("Permission denied"), "{}", err);
+}
+
+#[tokio::test]
+async fn graph_build_error_modules() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import './missing.ts';\nimport './bad.ts';")
+      .add_local_file("/bad.ts", "export const = 5;");
+  });
+
+  let err = ModuleGraph::build_with_specifiers(test_builder.graph_options())
+    .await
+    .err()
+    .unwrap();
+  let GraphBuildError::Modules(errors) = &err else {
+    panic!("unexpected error: {:#}", err);
+  };
+  let mut errors = errors
+    .iter()
+    .map(|e| {
+      (
+        e.kind,
+        e.specifier.to_string(),
+        e.referrer.as_ref().map(|r| r.specifier.to_string()),
+      )
+    })
+    .collect::<Vec<_>>();
+  errors.sort_by(|a, b| a.1.cmp(&b.1));
+  assert_eq!(
+    errors,
+    vec![
+      (
+        ModuleLoadErrorKind::Parse,
+        "file:///bad.ts".to_string(),
+        None
+      ),
+      (
+        ModuleLoadErrorKind::NotFound,
+        "file:///missing.ts".to_string(),
+        Some("file:///mod.ts".to_string()),
+      ),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_build_error_unmapped_modules() {
+  let mut test_builder = TestBuilder::new();
+  test_builder
+    .with_loader(|loader| {
+      loader.add_local_file("/mod.ts", "console.log(5);");
+    })
+    .add_module_specifier_mapping("file:///mod.deno.ts", "file:///mod.node.ts");
+
+  let err = ModuleGraph::build_with_specifiers(test_builder.graph_options())
+    .await
+    .err()
+    .unwrap();
+  match err {
+    GraphBuildError::UnmappedModules(specifiers) => assert_eq!(
+      specifiers,
+      vec![ModuleSpecifier::parse("file:///mod.deno.ts").unwrap()]
+    ),
+    err => panic!("unexpected error: {:#}", err),
+  }
+}
+
+#[tokio::test]
+async fn graph_resolve_dependency_with_types() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        concat!(
+          "// @deno-types='./pragma.d.ts'\n",
+          "import './pragma.js';\n",
+          "import 'https://localhost/header.js';\n",
+          "import './plain.js';\n",
+        ),
+      )
+      .add_local_file("/pragma.js", "export const a = 1;")
+      .add_local_file("/pragma.d.ts", "export const a: number;")
+      .add_local_file("/plain.js", "export const b = 1;")
+      .add_remote_file_with_headers(
+        "https://localhost/header.js",
+        "export const c = 1;",
+        &[
+          ("content-type", "application/javascript"),
+          ("x-typescript-types", "./header.d.ts"),
+        ],
+      )
+      .add_remote_file_with_headers(
+        "https://localhost/header.d.ts",
+        "export const c: number;",
+        &[("content-type", "application/typescript")],
+      );
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let referrer = ModuleSpecifier::parse("file:///mod.ts").unwrap();
+  let resolve = |value: &str| {
+    (
+      graph
+        .resolve_dependency(value, &referrer)
+        .unwrap()
+        .to_string(),
+      graph
+        .resolve_dependency_with_types(value, &referrer)
+        .unwrap()
+        .to_string(),
+    )
+  };
+  assert_eq!(
+    resolve("./pragma.js"),
+    (
+      "file:///pragma.js".to_string(),
+      "file:///pragma.d.ts".to_string()
+    )
+  );
+  assert_eq!(
+    resolve("https://localhost/header.js"),
+    (
+      "https://localhost/header.js".to_string(),
+      "https://localhost/header.d.ts".to_string()
+    )
+  );
+  assert_eq!(
+    resolve("./plain.js"),
+    (
+      "file:///plain.js".to_string(),
+      "file:///plain.js".to_string()
+    )
+  );
+  // the fallbacks are the same
+  assert_eq!(
+    graph
+      .resolve_dependency_with_types("./unknown.js", &referrer)
+      .unwrap()
+      .to_string(),
+    "file:///unknown.js"
+  );
+}
+
+#[tokio::test]
+async fn graph_external_packages_for() {
+  let mut test_builder = TestBuilder::new();
+  test_builder
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          "import './shared.ts';\nimport 'https://localhost/a/mod.ts';",
+        )
+        .add_local_file(
+          "/other.ts",
+          concat!(
+            "import './shared.ts';\n",
+            "// @deno-types='https://localhost/b/types.d.ts'\n",
+            "import './b.js';\n",
+            "import 'node:fs';\n",
+          ),
+        )
+        .add_local_file(
+          "/shared.ts",
+          "import 'https://localhost/shared/mod.ts';",
+        )
+        .add_local_file("/b.js", "export {};");
+    })
+    .add_entry_point("file:///other.ts")
+    .add_package_specifier_mapping(
+      "https://localhost/a/mod.ts",
+      "a",
+      Some("1.0.0"),
+      None,
+    )
+    .add_package_specifier_mapping(
+      "https://localhost/b/types.d.ts",
+      "b",
+      Some("2.0.0"),
+      None,
+    )
+    .add_package_specifier_mapping(
+      "https://localhost/shared/mod.ts",
+      "shared",
+      Some("^3.0.0"),
+      None,
+    );
+
+  let (graph, specifiers) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let package_names = |entry: &str| {
+    graph
+      .external_packages_for(
+        &ModuleSpecifier::parse(entry).unwrap(),
+        &specifiers,
+      )
+      .into_iter()
+      .map(|d| format!("{}@{}", d.name, d.version))
+      .collect::<Vec<_>>()
+  };
+  assert_eq!(
+    package_names("file:///mod.ts"),
+    vec!["a@1.0.0", "shared@^3.0.0"]
+  );
+  assert_eq!(
+    package_names("file:///other.ts"),
+    vec!["b@2.0.0", "shared@^3.0.0"]
+  );
+}
+
+#[tokio::test]
+async fn graph_canonicalize_json() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file(
+        "/mod.ts",
+        "import data from 'https://localhost/data.json' with { type: 'json' };",
+      )
+      .add_remote_file_with_headers(
+        "https://localhost/data.json",
+        r#"{"z":[2,1],  "a":{"y":1e400,"x":0.10}}"#,
+        &[("content-type", "application/json")],
+      );
+  });
+
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    canonicalize_json: true,
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  let module = graph
+    .get(&ModuleSpecifier::parse("https://localhost/data.json").unwrap())
+    .json()
+    .unwrap();
+  assert_eq!(
+    module.source.as_ref(),
+    concat!(
+      "{\n",
+      "  \"a\": {\n",
+      "    \"x\": 0.10,\n",
+      "    \"y\": 1e400\n",
+      "  },\n",
+      "  \"z\": [\n",
+      "    2,\n",
+      "    1\n",
+      "  ]\n",
+      "}",
+    )
+  );
+}
+
+#[tokio::test]
+async fn graph_external_dependencies() {
+  let mut test_builder = TestBuilder::new();
+  test_builder
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          concat!(
+            "import 'https://localhost/a/mod.ts';\n",
+            "import 'https://localhost/b/mod.ts';\n",
+            "import 'https://localhost/c/mod.ts';\n",
+            "import 'https://localhost/b/mod.ts';\n",
+          ),
+        )
+        .add_local_file("/c.ts", "export {};")
+        .add_remote_file("https://localhost/b/mod.ts", "export {};");
+    })
+    .add_package_specifier_mapping(
+      "https://localhost/a/mod.ts",
+      "a",
+      Some("1.0.0"),
+      None,
+    )
+    .add_module_specifier_mapping("https://localhost/c/mod.ts", "file:///c.ts");
+
+  let (graph, specifiers) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  let dependencies = graph
+    .external_dependencies(&specifiers)
+    .into_iter()
+    .map(|d| {
+      let mapped = match d.mapped {
+        Some(MappedSpecifier::Package(package)) => package.name,
+        Some(MappedSpecifier::Module(specifier)) => specifier.to_string(),
+        None => "".to_string(),
+      };
+      (d.specifier.to_string(), mapped)
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(
+    dependencies,
+    vec![
+      ("https://localhost/a/mod.ts".to_string(), "a".to_string()),
+      ("https://localhost/b/mod.ts".to_string(), "".to_string()),
+      (
+        "https://localhost/c/mod.ts".to_string(),
+        "file:///c.ts".to_string()
+      ),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn graph_cancellation() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader.add_local_file("/mod.ts", "console.log(5);");
+  });
+
+  let (handle, registration) = AbortHandle::new_pair();
+  handle.abort();
+  let err = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    cancellation: Some(registration),
+    ..test_builder.graph_options()
+  })
+  .await
+  .err()
+  .unwrap();
+  assert!(matches!(err, GraphBuildError::Cancelled));
+
+  // builds normally when not aborted
+  let (_handle, registration) = AbortHandle::new_pair();
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    cancellation: Some(registration),
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  assert_eq!(graph.all_modules().count(), 1);
+}
+
+#[tokio::test]
+async fn graph_css_asset_references() {
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import './styles/main.css';")
+      .add_local_file(
+        "/styles/main.css",
+        concat!(
+          "@import './theme.css';\n",
+          "/* url(./commented.png) */\n",
+          "body { background: url(\"../img/bg.png\"); }\n",
+          ".icon { background: url(data:image/png;base64,AAAA); }\n",
+          ".blur { filter: url(#blur); }\n",
+          ".missing { background: url(./missing.png); }\n",
+        ),
+      )
+      .add_local_file(
+        "/styles/theme.css",
+        "@font-face { src: url('../fonts/a.woff2'); }",
+      )
+      .add_local_file("/img/bg.png", "png")
+      .add_local_file("/fonts/a.woff2", "woff2");
+  });
+
+  let (graph, _) = ModuleGraph::build_with_specifiers(ModuleGraphOptions {
+    scan_css_assets: true,
+    ..test_builder.graph_options()
+  })
+  .await
+  .unwrap();
+  assert_eq!(
+    graph
+      .css_asset_references(
+        &ModuleSpecifier::parse("file:///styles/main.css").unwrap()
+      )
+      .into_iter()
+      .map(|s| s.to_string())
+      .collect::<Vec<_>>(),
+    vec![
+      "file:///styles/theme.css",
+      "file:///img/bg.png",
+      "file:///styles/missing.png",
+    ]
+  );
+  assert_eq!(
+    graph
+      .assets()
+      .keys()
+      .map(|s| s.as_str())
+      .collect::<Vec<_>>(),
+    vec![
+      "file:///fonts/a.woff2",
+      "file:///img/bg.png",
+      "file:///styles/main.css",
+      "file:///styles/theme.css",
+    ]
+  );
+  assert_eq!(
+    graph.warnings(),
+    vec![
+      "Could not find file:///styles/missing.png, which is referenced by file:///styles/main.css."
+    ]
+  );
+
+  // not loaded by default
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  assert_eq!(graph.assets().len(), 1);
+}
+
+#[tokio::test]
+async fn graph_validate_encodings() {
+  let utf16_bytes = [0xFF, 0xFE]
+    .into_iter()
+    .chain("export {};".encode_utf16().flat_map(|c| c.to_le_bytes()))
+    .collect::<Vec<u8>>();
+  let mut test_builder = TestBuilder::new();
+  test_builder.with_loader(|loader| {
+    loader
+      .add_local_file("/mod.ts", "import './bom.ts';\nimport './utf16.ts';")
+      .add_local_file("/bom.ts", "\u{FEFF}export {};")
+      .add_local_file_bytes("/utf16.ts", &utf16_bytes);
+  });
+
+  let (graph, _) =
+    ModuleGraph::build_with_specifiers(test_builder.graph_options())
+      .await
+      .unwrap();
+  assert_eq!(
+    graph.validate_encodings(),
+    vec![(
+      ModuleSpecifier::parse("file:///utf16.ts").unwrap(),
+      EncodingError { offset: 0 }
+    )]
+  );
+}
+
 fn get_shim_file_text(mut text: String) -> String {
   text.push('\n');
   text.push_str(
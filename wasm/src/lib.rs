@@ -75,6 +75,8 @@ pub struct TransformOptions {
   pub mappings: HashMap<ModuleSpecifier, MappedSpecifier>,
   pub target: ScriptTarget,
   pub import_map: Option<ModuleSpecifier>,
+  #[serde(default)]
+  pub jsr_url: Option<ModuleSpecifier>,
 }
 
 #[wasm_bindgen]
@@ -97,6 +99,7 @@ pub async fn transform(options: JsValue) -> Result<JsValue, JsValue> {
     specifier_mappings: options.mappings,
     target: options.target,
     import_map: options.import_map,
+    jsr_url: options.jsr_url,
   })
   .await
   .map_err(|err| format!("{:#}", err))?; // need to include the anyhow context